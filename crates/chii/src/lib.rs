@@ -1,5 +1,35 @@
 pub mod prelude {
-    pub struct Renderer {}
+    use std::{any::Any, ops::BitOr};
+
+    pub struct Renderer<'a> {
+        /// The number of buffer pixels per logical pixel of the surface being drawn.
+        pub scale: f64,
+
+        /// The renderer of the backend, e.g. a `nux::Renderer`, that widgets record their draw
+        /// commands on.
+        pub backend: Option<&'a mut dyn Any>,
+    }
+
+    impl<'a> Renderer<'a> {
+        pub fn new(scale: f64) -> Self {
+            Self {
+                scale,
+                backend: None,
+            }
+        }
+
+        pub fn backend(self, backend: &'a mut dyn Any) -> Self {
+            Self {
+                backend: Some(backend),
+                ..self
+            }
+        }
+
+        /// Returns the renderer of the backend, if it is of the requested type.
+        pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+            self.backend.as_deref_mut()?.downcast_mut::<T>()
+        }
+    }
 
     #[derive(Default, Clone)]
     pub enum Placement {
@@ -10,6 +40,364 @@ pub mod prelude {
         Left,
         Right,
         Windowed,
+        /// Anchored to a rectangle of another surface, e.g. a menu, a dropdown or a tooltip.
+        Popup(Popup),
+    }
+
+    /// The options of a surface placed as a window.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct Toplevel {
+        /// The title shown in the decorations and the task bar, the label when `None`.
+        pub title: Option<String>,
+        /// Identifies the application to the compositor, e.g. to find its desktop entry. The
+        /// label when `None`.
+        pub app_id: Option<String>,
+
+        /// The size limits of the window, in logical pixels, unbounded when `None`.
+        pub min_size: Option<(u32, u32)>,
+        pub max_size: Option<(u32, u32)>,
+
+        /// The label of the window this one belongs to, e.g. for a dialog.
+        pub parent: Option<String>,
+
+        /// Whether the window is first shown maximized.
+        pub maximized: bool,
+        /// Whether the window is first shown fullscreen, on the output of the layout.
+        pub fullscreen: bool,
+    }
+
+    impl Toplevel {
+        pub fn title(self, title: impl Into<String>) -> Self {
+            Self {
+                title: Some(title.into()),
+                ..self
+            }
+        }
+
+        pub fn app_id(self, app_id: impl Into<String>) -> Self {
+            Self {
+                app_id: Some(app_id.into()),
+                ..self
+            }
+        }
+
+        pub fn min_size(self, width: u32, height: u32) -> Self {
+            Self {
+                min_size: Some((width, height)),
+                ..self
+            }
+        }
+
+        pub fn max_size(self, width: u32, height: u32) -> Self {
+            Self {
+                max_size: Some((width, height)),
+                ..self
+            }
+        }
+
+        pub fn parent(self, parent: impl Into<String>) -> Self {
+            Self {
+                parent: Some(parent.into()),
+                ..self
+            }
+        }
+
+        pub fn maximized(self, maximized: bool) -> Self {
+            Self { maximized, ..self }
+        }
+
+        pub fn fullscreen(self, fullscreen: bool) -> Self {
+            Self { fullscreen, ..self }
+        }
+    }
+
+    /// A side or a corner of a rectangle, the center when `None`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum Direction {
+        #[default]
+        None,
+        Top,
+        Bottom,
+        Left,
+        Right,
+        TopLeft,
+        BottomLeft,
+        TopRight,
+        BottomRight,
+    }
+
+    /// How the compositor may move a popup that would not fit on its output, tried in this
+    /// order on each axis: flipped to the other side of its anchor, slid along it, then resized.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PopupConstraints {
+        pub flip_x: bool,
+        pub flip_y: bool,
+        pub slide_x: bool,
+        pub slide_y: bool,
+        pub resize_x: bool,
+        pub resize_y: bool,
+    }
+
+    impl Default for PopupConstraints {
+        fn default() -> Self {
+            Self {
+                flip_x: true,
+                flip_y: true,
+                slide_x: true,
+                slide_y: true,
+                resize_x: false,
+                resize_y: false,
+            }
+        }
+    }
+
+    /// The placement of a popup against its parent surface.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct Popup {
+        /// The label of the parent, a window, a layer surface or another popup.
+        pub parent: String,
+
+        /// The rectangle of the parent the popup is placed against, in buffer pixels of the
+        /// parent, e.g. the bounds of the button opening a menu.
+        pub anchor_rect: Rect,
+        /// The point of the rectangle the popup is placed at.
+        pub anchor: Direction,
+        /// The direction the popup extends to from that point.
+        pub gravity: Direction,
+        /// Moves the popup away from that point, in logical pixels.
+        pub offset: (i32, i32),
+
+        pub constraints: PopupConstraints,
+
+        /// Whether the popup takes the keyboard focus and is dismissed when clicking outside of
+        /// it, like a menu. The compositor only grants it in response to a press, so the popup
+        /// must be opened by one.
+        pub grab: bool,
+    }
+
+    impl Popup {
+        pub fn new(parent: impl Into<String>, anchor_rect: Rect) -> Self {
+            Self {
+                parent: parent.into(),
+                anchor_rect,
+                ..Default::default()
+            }
+        }
+
+        pub fn anchor(self, anchor: Direction) -> Self {
+            Self { anchor, ..self }
+        }
+
+        pub fn gravity(self, gravity: Direction) -> Self {
+            Self { gravity, ..self }
+        }
+
+        pub fn offset(self, x: i32, y: i32) -> Self {
+            Self {
+                offset: (x, y),
+                ..self
+            }
+        }
+
+        pub fn constraints(self, constraints: PopupConstraints) -> Self {
+            Self {
+                constraints,
+                ..self
+            }
+        }
+
+        pub fn grab(self, grab: bool) -> Self {
+            Self { grab, ..self }
+        }
+    }
+
+    /// The stacking layer of a surface that is not a window, from bottom to top.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum Layer {
+        Background,
+        Bottom,
+        #[default]
+        Top,
+        Overlay,
+    }
+
+    /// The edges of the output a surface is anchored to. A surface anchored to two opposite
+    /// edges is stretched between them when its size is 0, and a surface anchored to no edge
+    /// is centered.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Anchor {
+        pub top: bool,
+        pub bottom: bool,
+        pub left: bool,
+        pub right: bool,
+    }
+
+    impl Anchor {
+        pub const CENTER: Anchor = Anchor {
+            top: false,
+            bottom: false,
+            left: false,
+            right: false,
+        };
+
+        pub const TOP: Anchor = Anchor {
+            top: true,
+            ..Self::CENTER
+        };
+
+        pub const BOTTOM: Anchor = Anchor {
+            bottom: true,
+            ..Self::CENTER
+        };
+
+        pub const LEFT: Anchor = Anchor {
+            left: true,
+            ..Self::CENTER
+        };
+
+        pub const RIGHT: Anchor = Anchor {
+            right: true,
+            ..Self::CENTER
+        };
+
+        pub const ALL: Anchor = Anchor {
+            top: true,
+            bottom: true,
+            left: true,
+            right: true,
+        };
+
+        pub fn union(self, other: Anchor) -> Anchor {
+            Anchor {
+                top: self.top || other.top,
+                bottom: self.bottom || other.bottom,
+                left: self.left || other.left,
+                right: self.right || other.right,
+            }
+        }
+    }
+
+    impl BitOr for Anchor {
+        type Output = Anchor;
+
+        fn bitor(self, other: Anchor) -> Anchor {
+            self.union(other)
+        }
+    }
+
+    /// The distance between a surface and the anchored edges of the output.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Margin {
+        pub top: i32,
+        pub right: i32,
+        pub bottom: i32,
+        pub left: i32,
+    }
+
+    impl Margin {
+        pub fn new(top: i32, right: i32, bottom: i32, left: i32) -> Self {
+            Self {
+                top,
+                right,
+                bottom,
+                left,
+            }
+        }
+
+        pub fn all(margin: i32) -> Self {
+            Self::new(margin, margin, margin, margin)
+        }
+    }
+
+    /// The transform applied by the compositor to the content of an output.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum Transform {
+        #[default]
+        Normal,
+        Rotated90,
+        Rotated180,
+        Rotated270,
+        Flipped,
+        Flipped90,
+        Flipped180,
+        Flipped270,
+    }
+
+    /// A description of an output (a monitor) advertised by the compositor.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct Output {
+        /// The name of the output, e.g. `DP-1`, used to target it from a `Layout`.
+        pub name: String,
+        pub description: Option<String>,
+
+        pub make: String,
+        pub model: String,
+
+        /// The position of the output in the global compositor space, in logical pixels.
+        pub x: i32,
+        pub y: i32,
+
+        /// The size of the output, in logical pixels.
+        pub width: u32,
+        pub height: u32,
+
+        pub scale: i32,
+        pub transform: Transform,
+
+        /// The global name of the `wl_output`, which identifies the output while it is plugged in.
+        pub id: u32,
+    }
+
+    impl Output {
+        /// The key a `Layout` targets the output with: its name, or its id when the compositor
+        /// does not name outputs (`wl_output` before version 4).
+        pub fn key(&self) -> String {
+            if self.name.is_empty() {
+                format!("wl_output-{}", self.id)
+            } else {
+                self.name.clone()
+            }
+        }
+    }
+
+    /// A size along one axis of a surface, possibly relative to the output it is placed on.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Length {
+        /// A size in logical pixels. `0` lets the compositor stretch the surface between its
+        /// anchored edges.
+        Fixed(u32),
+        /// A percentage of the output's size.
+        Percent(f32),
+        /// The whole size of the output.
+        Fill,
+    }
+
+    impl Default for Length {
+        fn default() -> Self {
+            Self::Fixed(0)
+        }
+    }
+
+    impl From<u32> for Length {
+        fn from(length: u32) -> Self {
+            Self::Fixed(length)
+        }
+    }
+
+    impl Length {
+        /// Resolves the length against the size of the output along the same axis.
+        pub fn resolve(self, available: u32) -> u32 {
+            match self {
+                Self::Fixed(length) => length,
+                Self::Percent(percent) => (available as f32 * percent / 100.0).round() as u32,
+                Self::Fill => available,
+            }
+        }
+
+        /// Whether the length depends on the size of the output.
+        pub fn is_relative(&self) -> bool {
+            !matches!(self, Self::Fixed(_))
+        }
     }
 
     #[derive(Default, Clone)]
@@ -22,17 +410,348 @@ pub mod prelude {
 
     #[derive(Default, Clone)]
     pub struct Layout {
+        /// Offsets added to the left and top margins.
         pub x: u32,
         pub y: u32,
 
-        pub width: u32,
-        pub height: u32,
+        pub width: Length,
+        pub height: Length,
 
         pub placement: Placement,
+        /// The options of the window, when the placement is `Placement::Windowed`.
+        pub toplevel: Toplevel,
+
+        /// The key of the output to place the surface on, see `Output::key`, the compositor's
+        /// choice when `None`.
+        pub output: Option<String>,
+
+        /// The layer of the surface, the backend's default layer when `None`.
+        pub layer: Option<Layer>,
+        /// The anchor of the surface, derived from the placement when `None`.
+        pub anchor: Option<Anchor>,
+        pub margin: Margin,
+        /// The exclusive zone of the surface. When `None`, a surface placed on an edge reserves
+        /// its size along that edge. `-1` asks not to be moved by other exclusive zones and `0`
+        /// to be moved by them without reserving any space.
+        pub exclusive_zone: Option<i32>,
+
         pub keyboard_sensitivity: KeyboardSensitivity,
     }
 
-    pub struct Canvas {}
+    impl Layout {
+        /// Resolves the size of the surface against the size of its output.
+        pub fn size(&self, output: (u32, u32)) -> (u32, u32) {
+            (self.width.resolve(output.0), self.height.resolve(output.1))
+        }
+
+        /// Whether the size of the surface depends on the size of its output.
+        pub fn is_relative(&self) -> bool {
+            self.width.is_relative() || self.height.is_relative()
+        }
+
+        /// Resolves the exclusive zone of a surface of the given size.
+        pub fn resolve_exclusive_zone(&self, size: (u32, u32)) -> i32 {
+            self.exclusive_zone.unwrap_or(match self.placement {
+                Placement::Top | Placement::Bottom => size.1 as i32,
+                Placement::Left | Placement::Right => size.0 as i32,
+                _ => 0,
+            })
+        }
+    }
+
+    /// An axis aligned rectangle, in buffer pixels.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Rect {
+        pub x: u32,
+        pub y: u32,
+
+        pub width: u32,
+        pub height: u32,
+    }
+
+    impl Rect {
+        pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+            Self {
+                x,
+                y,
+                width,
+                height,
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.width == 0 || self.height == 0
+        }
+
+        /// Returns the smallest rectangle containing both rectangles.
+        pub fn union(&self, other: &Rect) -> Rect {
+            if self.is_empty() {
+                return *other;
+            }
+
+            if other.is_empty() {
+                return *self;
+            }
+
+            let x = self.x.min(other.x);
+            let y = self.y.min(other.y);
+
+            Rect {
+                x,
+                y,
+                width: (self.x + self.width).max(other.x + other.width) - x,
+                height: (self.y + self.height).max(other.y + other.height) - y,
+            }
+        }
+
+        /// Returns the overlapping part of both rectangles, which may be empty.
+        pub fn intersection(&self, other: &Rect) -> Rect {
+            let x = self.x.max(other.x);
+            let y = self.y.max(other.y);
+
+            Rect {
+                x,
+                y,
+                width: (self.x + self.width)
+                    .min(other.x + other.width)
+                    .saturating_sub(x),
+                height: (self.y + self.height)
+                    .min(other.y + other.height)
+                    .saturating_sub(y),
+            }
+        }
+    }
+
+    /// The regions of a widget that changed and must be redrawn.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub enum Damage {
+        #[default]
+        None,
+        Full,
+        Regions(Vec<Rect>),
+    }
+
+    impl Damage {
+        pub fn is_none(&self) -> bool {
+            match self {
+                Self::None => true,
+                Self::Full => false,
+                Self::Regions(rects) => rects.iter().all(Rect::is_empty),
+            }
+        }
+
+        /// Adds a damaged region.
+        pub fn add(&mut self, rect: Rect) {
+            if rect.is_empty() {
+                return;
+            }
+
+            match self {
+                Self::None => *self = Self::Regions(vec![rect]),
+                Self::Full => {}
+                Self::Regions(rects) => rects.push(rect),
+            }
+        }
+
+        /// Merges another damage into this one.
+        pub fn merge(&mut self, other: Damage) {
+            match other {
+                Self::None => {}
+                Self::Full => *self = Self::Full,
+                Self::Regions(rects) => {
+                    for rect in rects {
+                        self.add(rect);
+                    }
+                }
+            }
+        }
 
-    impl Canvas {}
+        /// Returns the damaged rectangles clipped to a buffer of the given size.
+        pub fn rects(&self, width: u32, height: u32) -> Vec<Rect> {
+            let buffer = Rect::new(0, 0, width, height);
+
+            match self {
+                Self::None => Vec::new(),
+                Self::Full => vec![buffer],
+                Self::Regions(rects) => rects
+                    .iter()
+                    .map(|rect| rect.intersection(&buffer))
+                    .filter(|rect| !rect.is_empty())
+                    .collect(),
+            }
+        }
+
+        /// Returns the smallest rectangle containing every damaged region, clipped to a buffer of
+        /// the given size.
+        pub fn bounds(&self, width: u32, height: u32) -> Option<Rect> {
+            self.rects(width, height)
+                .into_iter()
+                .reduce(|a, b| a.union(&b))
+        }
+    }
+
+    /// The target a widget draws on. Only the `clip` regions need to be redrawn, the rest of the
+    /// buffer still holds the content of a previous frame.
+    #[derive(Debug, Default, Clone)]
+    pub struct Canvas {
+        /// The size of the buffer, in buffer pixels.
+        pub width: u32,
+        pub height: u32,
+
+        /// The number of buffer pixels per logical pixel, which may be fractional.
+        pub scale: f64,
+
+        pub clip: Vec<Rect>,
+    }
+
+    impl Canvas {
+        pub fn new(width: u32, height: u32, scale: f64, clip: Vec<Rect>) -> Self {
+            Self {
+                width,
+                height,
+                scale,
+                clip,
+            }
+        }
+
+        /// The size of the buffer, in logical pixels.
+        pub fn logical_size(&self) -> (f64, f64) {
+            (
+                self.width as f64 / self.scale,
+                self.height as f64 / self.scale,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prelude::*;
+
+    #[test]
+    fn rect_union_and_intersection() {
+        let a = Rect::new(0, 0, 4, 4);
+        let b = Rect::new(2, 2, 4, 4);
+
+        assert_eq!(a.union(&b), Rect::new(0, 0, 6, 6));
+        assert_eq!(a.intersection(&b), Rect::new(2, 2, 2, 2));
+        assert!(a.intersection(&Rect::new(8, 8, 1, 1)).is_empty());
+        assert_eq!(a.union(&Rect::default()), a);
+    }
+
+    #[test]
+    fn damage_merges_regions() {
+        let mut damage = Damage::None;
+        assert!(damage.is_none());
+
+        damage.add(Rect::new(0, 0, 0, 4));
+        assert_eq!(damage, Damage::None);
+
+        damage.add(Rect::new(0, 0, 2, 2));
+        damage.merge(Damage::Regions(vec![Rect::new(6, 6, 4, 4)]));
+
+        assert_eq!(
+            damage.rects(8, 8),
+            vec![Rect::new(0, 0, 2, 2), Rect::new(6, 6, 2, 2)]
+        );
+        assert_eq!(damage.bounds(8, 8), Some(Rect::new(0, 0, 8, 8)));
+
+        damage.merge(Damage::Full);
+        damage.add(Rect::new(0, 0, 1, 1));
+        assert_eq!(damage, Damage::Full);
+        assert_eq!(Damage::None.bounds(8, 8), None);
+    }
+
+    #[test]
+    fn resolves_the_exclusive_zone_from_the_placement() {
+        let layout = |placement| Layout {
+            placement,
+            ..Default::default()
+        };
+
+        assert_eq!(layout(Placement::Top).resolve_exclusive_zone((800, 30)), 30);
+        assert_eq!(
+            layout(Placement::Bottom).resolve_exclusive_zone((800, 30)),
+            30
+        );
+        assert_eq!(
+            layout(Placement::Left).resolve_exclusive_zone((40, 600)),
+            40
+        );
+        assert_eq!(
+            layout(Placement::Right).resolve_exclusive_zone((40, 600)),
+            40
+        );
+        assert_eq!(layout(Placement::None).resolve_exclusive_zone((40, 600)), 0);
+        assert_eq!(
+            layout(Placement::Windowed).resolve_exclusive_zone((40, 600)),
+            0
+        );
+
+        let explicit = Layout {
+            exclusive_zone: Some(-1),
+            ..layout(Placement::Top)
+        };
+        assert_eq!(explicit.resolve_exclusive_zone((800, 30)), -1);
+    }
+
+    #[test]
+    fn anchors_combine() {
+        assert_eq!(
+            Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+            Anchor::ALL
+        );
+        assert_eq!(Anchor::CENTER | Anchor::LEFT, Anchor::LEFT);
+    }
+
+    #[test]
+    fn resolves_lengths() {
+        assert_eq!(Length::Fixed(30).resolve(1920), 30);
+        assert_eq!(Length::Percent(50.0).resolve(1920), 960);
+        assert_eq!(Length::Percent(33.3).resolve(100), 33);
+        assert_eq!(Length::Fill.resolve(1080), 1080);
+        assert_eq!(Length::from(12), Length::Fixed(12));
+
+        assert!(!Length::Fixed(30).is_relative());
+        assert!(Length::Percent(50.0).is_relative());
+        assert!(Length::Fill.is_relative());
+    }
+
+    #[test]
+    fn resolves_the_layout_size_against_the_output() {
+        let absolute = Layout {
+            width: Length::Fixed(200),
+            height: Length::Fixed(30),
+            ..Default::default()
+        };
+
+        assert_eq!(absolute.size((1920, 1080)), (200, 30));
+        assert!(!absolute.is_relative());
+
+        let relative = Layout {
+            width: Length::Fill,
+            height: Length::Percent(10.0),
+            ..Default::default()
+        };
+
+        assert_eq!(relative.size((1920, 1080)), (1920, 108));
+        assert_eq!(relative.size((1280, 720)), (1280, 72));
+        assert!(relative.is_relative());
+    }
+
+    #[test]
+    fn keys_outputs_by_name_or_id() {
+        let named = Output {
+            name: "DP-1".to_string(),
+            id: 42,
+            ..Default::default()
+        };
+        assert_eq!(named.key(), "DP-1");
+
+        let unnamed = Output {
+            id: 42,
+            ..Default::default()
+        };
+        assert_eq!(unnamed.key(), "wl_output-42");
+    }
 }
@@ -11,11 +11,15 @@ pub(crate) enum ApplicationDirective<Message> {
 
     Submit(Element<Message>),
     Close(String),
+    Redraw(String),
+    Window(String, WindowRequest),
+    Clipboard(ClipboardRequest),
 }
 
 pub(crate) type StateFn<State> = Box<dyn Fn() -> State + Send>;
 pub(crate) type UpdateFn<State, Message> = Box<dyn Fn(&mut State, Message) -> Task<Message> + Send>;
 pub(crate) type ViewFn<State, Message> = Box<dyn Fn(&State) -> Element<Message> + Send>;
+pub(crate) type EventFn<Message> = Box<dyn Fn(ApplicationEvent) -> Option<Message> + Send>;
 
 /// The `Application` struct represents a UI application with a state, update function, and view function.
 /// Example usage:
@@ -27,7 +31,7 @@ pub(crate) type ViewFn<State, Message> = Box<dyn Fn(&State) -> Element<Message>
 ///
 /// let application =
 ///     Application::new(State::default, State::update, State::view)
-///     .initial_task(Task::msg(Message::Nothing));
+///     .task(Task::msg(Message::Nothing));
 ///
 /// enum Message {
 ///     Nothing,
@@ -50,6 +54,8 @@ pub struct Application<State, Message> {
     pub(crate) state: StateFn<State>,
     pub(crate) update: UpdateFn<State, Message>,
     pub(crate) view: ViewFn<State, Message>,
+    pub(crate) on_event: EventFn<Message>,
+    pub(crate) keybindings: Keybindings<Message>,
 
     pub(crate) initial_task: Option<Task<Message>>,
 }
@@ -65,6 +71,8 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
             state: Box::new(state),
             update: Box::new(update),
             view: Box::new(view),
+            on_event: Box::new(|_| None),
+            keybindings: Keybindings::new(),
             initial_task: None,
         }
     }
@@ -77,8 +85,31 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
         }
     }
 
+    /// Maps the application events sent by the backend to messages. Events mapped to `None` are
+    /// ignored, which is the default.
+    pub fn on_event(
+        self,
+        on_event: impl Fn(ApplicationEvent) -> Option<Message> + 'static + Send,
+    ) -> Self {
+        Self {
+            on_event: Box::new(on_event),
+            ..self
+        }
+    }
+
+    /// Sets the keyboard shortcuts of the application, matched by the backend after the ones of
+    /// the focused widget. A key press completing a binding sends its message, and neither the
+    /// press nor the ones of a pending sequence reach the focused widget.
+    pub fn keybindings(self, keybindings: Keybindings<Message>) -> Self {
+        Self {
+            keybindings,
+            ..self
+        }
+    }
+
     pub(crate) async fn jobs<T: Backend<Message>>(
         self,
+        config: T::Config,
         on_error: impl Fn(Report) -> Message + 'static + Send + Sync,
     ) -> Result<(
         JoinHandle<Result<()>>,
@@ -88,6 +119,7 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
         let (msg_submitter, mut msg_server) = channel::<Message>();
         let (directive_submitter, mut directive_server) =
             channel::<ApplicationDirective<Message>>();
+        let (event_submitter, mut event_server) = channel::<ApplicationEvent>();
 
         let (pool, tasks) = {
             let pool = TaskPool::<Message>::new();
@@ -105,10 +137,19 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
 
         let mut state = (self.state)();
 
-        let backend = T::new(msg_submitter.clone()).await?;
+        let backend = T::new(
+            config,
+            msg_submitter.clone(),
+            event_submitter,
+            self.keybindings,
+        )
+        .await?;
 
         let backend_submitter = backend.submitter();
         let backend_closer = backend.closer();
+        let backend_redrawer = backend.redrawer();
+        let backend_windower = backend.windower();
+        let backend_clipboard = backend.clipboard();
 
         let server = tokio::spawn(async move {
             tracing::info!("Server started");
@@ -147,6 +188,13 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
 
                         labels = new_labels;
                     }
+                    Ok(event) = event_server.recv() => {
+                        if let Some(message) = (self.on_event)(event) {
+                            msg_submitter.submit(message).unwrap_or_else(|e| {
+                                tracing::error!("Failed to submit message: {}", e);
+                            });
+                        }
+                    }
                     Ok(directive) = directive_server.recv() => {
                         match directive {
                             ApplicationDirective::Stop => break,
@@ -164,6 +212,21 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
                                     tracing::error!("Failed to submit a close request for this label: {}", e);
                                 });
                             }
+                            ApplicationDirective::Redraw(label) => {
+                                backend_redrawer.submit(label).unwrap_or_else(|e| {
+                                    tracing::error!("Failed to submit a redraw request for this label: {}", e);
+                                });
+                            }
+                            ApplicationDirective::Window(label, request) => {
+                                backend_windower.submit((label, request)).unwrap_or_else(|e| {
+                                    tracing::error!("Failed to submit a window request for this label: {}", e);
+                                });
+                            }
+                            ApplicationDirective::Clipboard(request) => {
+                                backend_clipboard.submit(request).unwrap_or_else(|e| {
+                                    tracing::error!("Failed to submit a clipboard request: {}", e);
+                                });
+                            }
                         }
                     }
                 }
@@ -188,7 +251,7 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
     ///     Application::new(State::default, State::update, State::view)
-    ///         .initial_task(Task::msg(Message::Nothing))
+    ///         .task(Task::msg(Message::Nothing))
     ///         .run::<EmptyBackend<Message>>(|e| Message::Error(Arc::new(e)))
     ///         .await
     /// }
@@ -213,8 +276,52 @@ impl<State: Send + 'static, Message: 'static + Send + Sync> Application<State, M
     pub async fn run<T: Backend<Message> + 'static>(
         self,
         on_error: impl Fn(Report) -> Message + 'static + Send + Sync,
+    ) -> Result<()>
+    where
+        T::Config: Default,
+    {
+        self.run_with::<T>(T::Config::default(), on_error).await
+    }
+
+    /// Runs the application with the specified backend and backend configuration.
+    /// Example usage:
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use hej::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Application::new(State::default, State::update, State::view)
+    ///         .task(Task::msg(Message::Nothing))
+    ///         .run_with::<EmptyBackend<Message>>((), |e| Message::Error(Arc::new(e)))
+    ///         .await
+    /// }
+    ///
+    /// enum Message {
+    ///     Nothing,
+    ///     Error(Arc<Report>),
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct State {}
+    ///
+    /// impl State {
+    ///     fn update(&mut self, _message: Message) -> Task<Message> {
+    ///         Task::stop()
+    ///     }
+    ///     fn view(&self) -> Element<Message> {
+    ///         Element::empty()
+    ///     }
+    /// }
+    /// ```
+    pub async fn run_with<T: Backend<Message> + 'static>(
+        self,
+        config: T::Config,
+        on_error: impl Fn(Report) -> Message + 'static + Send + Sync,
     ) -> Result<()> {
-        let (server, backend, pool) = self.jobs::<T>(on_error).await?;
+        let (server, backend, pool) = self.jobs::<T>(config, on_error).await?;
 
         let ctrl_c = tokio::signal::ctrl_c();
 
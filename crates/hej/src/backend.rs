@@ -7,9 +7,18 @@ use crate::prelude::*;
 /// The BackendTrait defines the interface for a backend that can receives elements to render and
 /// manage.
 pub trait Backend<Message>: Send + Sync {
-    /// Creates a new instance of the backend.
+    /// The configuration of the backend, provided to `Application::run_with`.
+    type Config: Send + 'static;
+
+    /// Creates a new instance of the backend. Application events are sent through
+    /// `event_submitter`. Key presses are matched against the shortcuts of the focused widget,
+    /// then against `keybindings`, before they reach the widget. The presses they consume, and
+    /// their releases, are not delivered.
     fn new(
+        config: Self::Config,
         msg_submitter: Submitter<Message>,
+        event_submitter: Submitter<ApplicationEvent>,
+        keybindings: Keybindings<Message>,
     ) -> impl Future<Output = Result<Self>> + Send + 'static
     where
         Self: Sized;
@@ -19,6 +28,15 @@ pub trait Backend<Message>: Send + Sync {
 
     fn closer(&self) -> Submitter<String>;
 
+    /// Returns a clone of the sender used to request a redraw of the element with a given label.
+    fn redrawer(&self) -> Submitter<String>;
+
+    /// Returns a clone of the sender used to send a request about the window with a given label.
+    fn windower(&self) -> Submitter<(String, WindowRequest)>;
+
+    /// Returns a clone of the sender used to read and write the selections.
+    fn clipboard(&self) -> Submitter<ClipboardRequest>;
+
     /// Runs the backend, processing elements and handling messages.
     fn run(self) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
     where
@@ -28,25 +46,54 @@ pub trait Backend<Message>: Send + Sync {
 /// An empty backend implementation that does not perform any operations.
 pub struct EmptyBackend<Message> {
     pub(crate) _msg_submitter: Submitter<Message>,
+    pub(crate) _event_submitter: Submitter<ApplicationEvent>,
+    pub(crate) _keybindings: Keybindings<Message>,
 
     pub(crate) submitter: Submitter<Element<Message>>,
     pub(crate) server: Server<Element<Message>>,
 
     pub(crate) closer: Submitter<String>,
     pub(crate) _closer_server: Server<String>,
+
+    pub(crate) redrawer: Submitter<String>,
+    pub(crate) _redrawer_server: Server<String>,
+
+    pub(crate) windower: Submitter<(String, WindowRequest)>,
+    pub(crate) _windower_server: Server<(String, WindowRequest)>,
+
+    pub(crate) clipboard: Submitter<ClipboardRequest>,
+    pub(crate) _clipboard_server: Server<ClipboardRequest>,
 }
 
 impl<Message: 'static + Send + Sync> Backend<Message> for EmptyBackend<Message> {
-    async fn new(msg_submitter: Submitter<Message>) -> Result<Self> {
+    type Config = ();
+
+    async fn new(
+        _config: Self::Config,
+        msg_submitter: Submitter<Message>,
+        event_submitter: Submitter<ApplicationEvent>,
+        keybindings: Keybindings<Message>,
+    ) -> Result<Self> {
         let (submitter, server) = channel();
         let (closer, _closer_server) = channel();
+        let (redrawer, _redrawer_server) = channel();
+        let (windower, _windower_server) = channel();
+        let (clipboard, _clipboard_server) = channel();
 
         Ok(Self {
             _msg_submitter: msg_submitter,
+            _event_submitter: event_submitter,
+            _keybindings: keybindings,
             submitter,
             server,
             closer,
             _closer_server,
+            redrawer,
+            _redrawer_server,
+            windower,
+            _windower_server,
+            clipboard,
+            _clipboard_server,
         })
     }
 
@@ -54,6 +101,18 @@ impl<Message: 'static + Send + Sync> Backend<Message> for EmptyBackend<Message>
         self.closer.clone()
     }
 
+    fn redrawer(&self) -> Submitter<String> {
+        self.redrawer.clone()
+    }
+
+    fn windower(&self) -> Submitter<(String, WindowRequest)> {
+        self.windower.clone()
+    }
+
+    fn clipboard(&self) -> Submitter<ClipboardRequest> {
+        self.clipboard.clone()
+    }
+
     fn submitter(&self) -> Submitter<Element<Message>> {
         self.submitter.clone()
     }
@@ -0,0 +1,134 @@
+//! This module defines the data an application copies to the clipboard or the primary selection
+//! with `Task::write_clipboard`, and the requests the backend receives to read and write them.
+
+use std::sync::Arc;
+
+use tokio::sync::oneshot::Sender;
+
+use crate::prelude::*;
+
+/// The MIME types text is offered with, the ones older clients still ask for included.
+pub const TEXT_MIME_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+];
+
+/// A selection shared between applications.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The clipboard, written by copying and read by pasting.
+    #[default]
+    Clipboard,
+
+    /// The primary selection, written by selecting text and read by a middle click.
+    Primary,
+}
+
+/// The data put on a selection, offered in several MIME types so that every client can paste
+/// the representation it understands.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClipboardData {
+    pub(crate) offers: Vec<(String, Arc<[u8]>)>,
+}
+
+impl ClipboardData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offers text in all the MIME types of `TEXT_MIME_TYPES`.
+    pub fn text(text: impl Into<String>) -> Self {
+        let data: Arc<[u8]> = text.into().into_bytes().into();
+
+        Self {
+            offers: TEXT_MIME_TYPES
+                .iter()
+                .map(|mime_type| (mime_type.to_string(), data.clone()))
+                .collect(),
+        }
+    }
+
+    /// Offers the data in another MIME type, replacing the data already offered in it.
+    pub fn with(mut self, mime_type: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        let mime_type = mime_type.into();
+
+        self.offers.retain(|(offered, _)| *offered != mime_type);
+        self.offers.push((mime_type, data.into().into()));
+
+        self
+    }
+
+    /// The MIME types the data is offered in, in the order they were added.
+    pub fn mime_types(&self) -> impl Iterator<Item = &str> {
+        self.offers.iter().map(|(mime_type, _)| mime_type.as_str())
+    }
+
+    /// The data offered in a MIME type.
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.offers
+            .iter()
+            .find(|(offered, _)| offered == mime_type)
+            .map(|(_, data)| &**data)
+    }
+}
+
+/// A request of the application about a selection, sent to the backend.
+pub enum ClipboardRequest {
+    /// Reads the selection in a MIME type. The data, or the reason it could not be read, e.g.
+    /// the selection is empty or not offered in this MIME type, is sent to `reply`.
+    Read {
+        selection: Selection,
+        mime_type: String,
+        reply: Sender<Result<Vec<u8>>>,
+    },
+
+    /// Puts data on the selection, which the backend offers until another client replaces it.
+    Write {
+        selection: Selection,
+        data: ClipboardData,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_text_in_every_text_mime_type() {
+        let data = ClipboardData::text("hej");
+
+        assert_eq!(data.mime_types().collect::<Vec<_>>(), TEXT_MIME_TYPES);
+
+        for mime_type in TEXT_MIME_TYPES {
+            assert_eq!(data.get(mime_type), Some(&b"hej"[..]));
+        }
+
+        assert_eq!(data.get("text/html"), None);
+    }
+
+    #[test]
+    fn replaces_the_data_of_a_mime_type() {
+        let data = ClipboardData::new()
+            .with("text/html", "<b>a</b>")
+            .with("image/png", [0x89, b'P'])
+            .with("text/html", "<i>b</i>");
+
+        assert_eq!(
+            data.mime_types().collect::<Vec<_>>(),
+            ["image/png", "text/html"]
+        );
+        assert_eq!(data.get("text/html"), Some(&b"<i>b</i>"[..]));
+        assert_eq!(data.get("image/png"), Some(&[0x89, b'P'][..]));
+    }
+
+    #[test]
+    fn offers_nothing_by_default() {
+        let data = ClipboardData::new();
+
+        assert_eq!(data.mime_types().next(), None);
+        assert_eq!(data.get("text/plain"), None);
+    }
+}
@@ -0,0 +1,59 @@
+//! This module defines `Cursor`, the shape of the pointer that a widget asks for while the
+//! pointer is over it.
+
+/// The shape of the pointer, named after the CSS cursors. It is returned by `Widget::cursor`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cursor {
+    /// The default arrow.
+    #[default]
+    Default,
+
+    /// A hand, over a link or a button.
+    Pointer,
+
+    /// An I-beam, over selectable text.
+    Text,
+
+    Crosshair,
+    Help,
+    ContextMenu,
+
+    /// The application is busy but can still be interacted with.
+    Progress,
+
+    /// The application is busy and cannot be interacted with.
+    Wait,
+
+    NotAllowed,
+
+    /// Something can be moved.
+    Move,
+
+    /// Something can be grabbed, and is being grabbed.
+    Grab,
+    Grabbing,
+
+    /// An edge or a corner of a window being resized.
+    NResize,
+    EResize,
+    SResize,
+    WResize,
+    NeResize,
+    NwResize,
+    SeResize,
+    SwResize,
+
+    /// A bidirectional resize, e.g. of a splitter.
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+
+    ZoomIn,
+    ZoomOut,
+
+    /// No cursor is shown.
+    Hidden,
+}
@@ -1,5 +1,7 @@
 //! Element module for the GUI framework.
 
+use std::sync::Arc;
+
 use eyre::OptionExt;
 
 use crate::prelude::*;
@@ -29,11 +31,48 @@ impl<Message: 'static + Send + Sync> Element<Message> {
         self.widget.on_event(event, client)
     }
 
+    /// This function matches a key press against the shortcuts of the widget, and returns
+    /// whether it consumed the press.
+    pub fn on_shortcut(&mut self, event: &KeyEvent, client: Submitter<Message>) -> Result<bool> {
+        self.widget.on_shortcut(event, client)
+    }
+
     /// This function is called to render the widget using the provided renderer.
     pub fn draw(&self, canvas: Canvas, renderer: &mut Renderer) -> Result<()> {
         self.widget.draw(canvas, renderer)
     }
 
+    /// This function returns the regions of the widget that changed since the last call.
+    pub fn damage(&mut self) -> Damage {
+        self.widget.damage()
+    }
+
+    /// This function returns the state of the text field of the widget, if it accepts text input.
+    pub fn text_input(&self) -> Option<TextInput> {
+        self.widget.text_input()
+    }
+
+    /// This function returns whether the widget handles touch events itself.
+    pub fn accepts_touch(&self) -> bool {
+        self.widget.accepts_touch()
+    }
+
+    /// This function returns the cursor to show while the pointer is over the widget.
+    pub fn cursor(&self) -> Cursor {
+        self.widget.cursor()
+    }
+
+    /// This function returns the interactive operation the widget starts on its window, and
+    /// clears it.
+    pub fn window_action(&mut self) -> Option<WindowAction> {
+        self.widget.window_action()
+    }
+
+    /// This function returns whether the widget handles the requests to close its window.
+    pub fn handles_close(&self) -> bool {
+        self.widget.handles_close()
+    }
+
     /// This function returns a reference to the widget as a trait object.
     pub fn downcast_ref<'a, T: Widget<Message>>(&'a self) -> Result<&'a T> {
         self.widget
@@ -68,8 +107,37 @@ impl<Message: 'static + Send + Sync> Element<Message> {
         self,
         map: Map<Message, NewMessage>,
     ) -> Element<NewMessage> {
-        Element {
-            widget: Box::new(MapWidget::new(self.widget, map)),
+        // The backend must still recognize a per output widget, so its instances are mapped.
+        match self.downcast::<PerOutputWidget<Message>>() {
+            Ok(widget) => (*widget).map(map).element(),
+            Err(element) => Element {
+                widget: Box::new(MapWidget::new(element.widget, map)),
+            },
+        }
+    }
+
+    /// Sends a message when the surface of this element is asked to close, by the compositor or
+    /// its decorations, instead of closing it. Surfaces the compositor already removed, i.e.
+    /// closed layers and dismissed popups, are still destroyed.
+    pub fn on_close(self, on_close: impl Fn() -> Message + 'static + Send + Sync) -> Self {
+        let on_close: Arc<dyn Fn() -> Message + Send + Sync> = Arc::new(on_close);
+
+        // Like a map, it must apply to the instances of a per output widget.
+        match self.downcast::<PerOutputWidget<Message>>() {
+            Ok(widget) => {
+                let template = widget.builder;
+
+                let builder: OutputBuilder<Message> = Arc::new(move |output: &Output| {
+                    OnCloseWidget::new(template(output).widget, on_close.clone()).element()
+                });
+
+                PerOutputWidget {
+                    label: widget.label,
+                    builder,
+                }
+                .element()
+            }
+            Err(element) => OnCloseWidget::new(element.widget, on_close).element(),
         }
     }
 
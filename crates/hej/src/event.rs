@@ -1,5 +1,58 @@
-//! This module only defines the `Event` enum, which represents various events that can occur for
-//! a widget.
+//! This module defines the `Event` enum, which represents various events that can occur for a
+//! widget, and the `ApplicationEvent` enum for events that concern the whole application.
+
+use crate::prelude::*;
+
+/// Identifies the seat an input event comes from, i.e. the group of input devices used by one
+/// person. Events of different seats interleave, so a widget tracking input state, e.g. a held
+/// button or a touch point, should keep it per seat.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeatId(pub u32);
+
+/// The device a scroll comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrollSource {
+    /// A wheel, scrolling by steps.
+    Wheel,
+
+    /// Fingers on a touchpad, ending with `Event::PointerScrollStopped`.
+    Finger,
+
+    /// A continuous movement that is not from fingers, e.g. a trackpoint.
+    Continuous,
+
+    /// A wheel tilted sideways.
+    WheelTilt,
+}
+
+/// The `ApplicationEvent` enum represents events sent by the backend that are not related to a
+/// particular widget. They are mapped to messages by `Application::on_event`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplicationEvent {
+    /// A new output was advertised by the compositor.
+    OutputAdded(Output),
+
+    /// The description of an output changed, e.g. its mode or scale.
+    OutputChanged(Output),
+
+    /// The output with this name was removed.
+    OutputRemoved(String),
+
+    /// The popup with this label was dismissed by the compositor, e.g. a click outside of a
+    /// grabbing popup, and its surface was destroyed. The application should stop showing it.
+    PopupDismissed { label: String },
+
+    /// The compositor configured the window with this label, e.g. it was maximized or it lost
+    /// the focus.
+    WindowConfigured { label: String, state: WindowState },
+
+    /// Another client, or the application itself, put data on a selection, offered in these
+    /// MIME types. They are empty when the selection was cleared.
+    SelectionChanged {
+        selection: Selection,
+        mime_types: Vec<String>,
+    },
+}
 
 /// The `Event` enum represents different types of events that can occur for a widget.
 #[derive(Debug, Clone, PartialEq)]
@@ -13,50 +66,85 @@ pub enum Event {
         height: u32,
     },
 
-    /// Render event that indicates the widget should be redrawn. This event should only be used
-    /// on the master Widget provided to the `Backend`.
+    /// The scale of the widget's surface changed. It is the number of buffer pixels per logical
+    /// pixel, and may be fractional when the compositor supports fractional scaling.
+    ScaleChanged {
+        scale: f64,
+    },
+
+    /// Render event that indicates the compositor is ready for a new frame of the widget's
+    /// surface. It is only sent to surfaces that requested a redraw, a widget can report damage
+    /// in response to keep animating.
     Render,
 
     /// The keyboard entered the widget, meaning it is now focused and can receive keyboard input.
-    KeyboardEntered,
+    KeyboardEntered {
+        seat: SeatId,
+    },
 
     /// The keyboard left the widget, meaning it is no longer focused and will not receive keyboard input.
-    KeyboardLeaved,
-
-    /// Key events for keyboard input.
-    KeyPressed {
-        key: u32,
+    KeyboardLeaved {
+        seat: SeatId,
     },
 
-    /// Key events for keyboard input when a key is released.
-    KeyReleased {
-        key: u32,
-    },
+    /// A key was pressed, or is held down and repeats. Like the other key events, it is only sent
+    /// to the widget of the surface the keyboard of its seat focuses. Focus is not tracked below
+    /// the surface: a widget made of other widgets forwards the keys to the child it focuses.
+    KeyPressed(KeyEvent),
+
+    /// A key was released.
+    KeyReleased(KeyEvent),
 
     /// Key modifiers changed, indicating a change in the state of modifier keys (Ctrl, Alt, Shift, etc.).
     KeyModifiersChanged {
-        ctrl: bool,
-        alt: bool,
-        shift: bool,
-        caps_lock: bool,
-        logo: bool,
-        num_lock: bool,
+        seat: SeatId,
+        modifiers: Modifiers,
+    },
+
+    /// The input method is composing text, which is shown at the cursor in place of the previous
+    /// preedit text. An empty text removes it. The cursor is a byte range in the text, hidden
+    /// when `None`.
+    Preedit {
+        seat: SeatId,
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+
+    /// The input method inserts text at the cursor, replacing the selection and the preedit text.
+    Commit {
+        seat: SeatId,
+        text: String,
+    },
+
+    /// The input method deletes bytes around the cursor, before and after it. It is sent before
+    /// the `Commit` it goes with.
+    DeleteSurrounding {
+        seat: SeatId,
+        before: usize,
+        after: usize,
     },
 
     /// The pointer entered the widget, meaning it is now focused and can receive pointer input.
-    PointerEntered,
+    PointerEntered {
+        seat: SeatId,
+    },
 
     /// The pointer left the widget, meaning it is no longer focused and will not receive pointer input.
-    PointerLeaved,
+    PointerLeaved {
+        seat: SeatId,
+    },
 
-    /// Pointer events for pointer input when the pointer is moved.
+    /// Pointer events for pointer input when the pointer is moved. Pointer positions are in
+    /// buffer pixels, like the canvas the widget draws on.
     PointerMoved {
+        seat: SeatId,
         x: f64,
         y: f64,
     },
 
     /// Pointer events for pointer input when a button is pressed.
     PointerPressed {
+        seat: SeatId,
         x: f64,
         y: f64,
         button: u32,
@@ -64,17 +152,169 @@ pub enum Event {
 
     /// Pointer events for pointer input when a button is released.
     PointerReleased {
+        seat: SeatId,
         x: f64,
         y: f64,
         button: u32,
     },
 
-    /// Pointer events for pointer input when the pointer is scrolled.
+    /// The button held by the pointer was lifted without completing an action, e.g. the
+    /// compositor took over the touch point emulating the pointer. The press must be forgotten,
+    /// it is not a click.
+    PointerCancelled {
+        seat: SeatId,
+    },
+
+    /// Pointer events for pointer input when the pointer is scrolled. The deltas are in buffer
+    /// pixels, and the steps count the notches of a wheel, they are zero for continuous sources.
     PointerScrolled {
+        seat: SeatId,
         x: f64,
         y: f64,
 
         delta_x: f64,
         delta_y: f64,
+
+        steps_x: i32,
+        steps_y: i32,
+
+        /// The device that scrolled, `None` when the compositor does not tell.
+        source: Option<ScrollSource>,
+    },
+
+    /// A continuous scroll stopped on these axes, e.g. the fingers were lifted from the touchpad.
+    /// A widget may start kinetic scrolling with the last deltas.
+    PointerScrollStopped {
+        seat: SeatId,
+        horizontal: bool,
+        vertical: bool,
+    },
+
+    /// A swipe started on the touchpad with this many fingers, over the widget.
+    SwipeBegan {
+        seat: SeatId,
+        fingers: u32,
+    },
+
+    /// The fingers of the swipe moved, by deltas in buffer pixels since the previous update.
+    SwipeUpdated {
+        seat: SeatId,
+        delta_x: f64,
+        delta_y: f64,
+    },
+
+    /// The swipe ended, or was cancelled, e.g. when a finger was added.
+    SwipeEnded {
+        seat: SeatId,
+        cancelled: bool,
+    },
+
+    /// A pinch started on the touchpad with this many fingers, over the widget.
+    PinchBegan {
+        seat: SeatId,
+        fingers: u32,
+    },
+
+    /// The fingers of the pinch moved. The deltas of their center are in buffer pixels since the
+    /// previous update, the scale is relative to the start of the pinch, and the rotation is in
+    /// degrees clockwise since the previous update.
+    PinchUpdated {
+        seat: SeatId,
+        delta_x: f64,
+        delta_y: f64,
+        scale: f64,
+        rotation: f64,
+    },
+
+    /// The pinch ended, or was cancelled.
+    PinchEnded {
+        seat: SeatId,
+        cancelled: bool,
+    },
+
+    /// Fingers were put on the touchpad without moving, e.g. to stop kinetic scrolling.
+    HoldBegan {
+        seat: SeatId,
+        fingers: u32,
+    },
+
+    /// The fingers were lifted, or the hold was cancelled because they moved.
+    HoldEnded {
+        seat: SeatId,
+        cancelled: bool,
     },
+
+    /// A gesture recognized from the pointer events, see `GestureRecognizer`.
+    Gesture {
+        seat: SeatId,
+        gesture: Gesture,
+    },
+
+    /// A touch point appeared on the widget. Its id identifies it in the following events until
+    /// it is lifted or cancelled, and may then be reused. Positions are in buffer pixels.
+    TouchDown {
+        seat: SeatId,
+        id: i32,
+        x: f64,
+        y: f64,
+    },
+
+    /// A touch point moved.
+    TouchMoved {
+        seat: SeatId,
+        id: i32,
+        x: f64,
+        y: f64,
+    },
+
+    /// A touch point was lifted.
+    TouchUp {
+        seat: SeatId,
+        id: i32,
+    },
+
+    /// The compositor took over every touch point, e.g. to handle a gesture, so they must be
+    /// forgotten without being considered lifted.
+    TouchCancelled {
+        seat: SeatId,
+    },
+}
+
+impl Event {
+    /// The seat of an input event, `None` for the other events.
+    pub fn seat(&self) -> Option<SeatId> {
+        match self {
+            Self::KeyPressed(event) | Self::KeyReleased(event) => Some(event.seat),
+
+            Self::KeyboardEntered { seat }
+            | Self::KeyboardLeaved { seat }
+            | Self::KeyModifiersChanged { seat, .. }
+            | Self::Preedit { seat, .. }
+            | Self::Commit { seat, .. }
+            | Self::DeleteSurrounding { seat, .. }
+            | Self::PointerEntered { seat }
+            | Self::PointerLeaved { seat }
+            | Self::PointerMoved { seat, .. }
+            | Self::PointerPressed { seat, .. }
+            | Self::PointerReleased { seat, .. }
+            | Self::PointerCancelled { seat }
+            | Self::PointerScrolled { seat, .. }
+            | Self::PointerScrollStopped { seat, .. }
+            | Self::SwipeBegan { seat, .. }
+            | Self::SwipeUpdated { seat, .. }
+            | Self::SwipeEnded { seat, .. }
+            | Self::PinchBegan { seat, .. }
+            | Self::PinchUpdated { seat, .. }
+            | Self::PinchEnded { seat, .. }
+            | Self::HoldBegan { seat, .. }
+            | Self::HoldEnded { seat, .. }
+            | Self::Gesture { seat, .. }
+            | Self::TouchDown { seat, .. }
+            | Self::TouchMoved { seat, .. }
+            | Self::TouchUp { seat, .. }
+            | Self::TouchCancelled { seat } => Some(*seat),
+
+            Self::Close | Self::Configure { .. } | Self::ScaleChanged { .. } | Self::Render => None,
+        }
+    }
 }
@@ -0,0 +1,426 @@
+//! This module defines the gesture layer, which synthesizes clicks, long presses and drags from
+//! the raw pointer events. The backend runs a `GestureRecognizer` for every surface and sends the
+//! recognized gestures to the widget as `Event::Gesture`, right after the pointer event that
+//! completed them.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::prelude::*;
+
+/// The timings and distances used to recognize gestures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureConfig {
+    /// The longest delay between two clicks counted as a double or triple click.
+    pub multi_click_interval: Duration,
+
+    /// How long a button must be held, without moving, to make a long press.
+    pub long_press_delay: Duration,
+
+    /// How far the pointer must move, in buffer pixels, while a button is held to start a drag.
+    /// Clicks further apart than this are not counted as a double click either.
+    pub drag_threshold: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            multi_click_interval: Duration::from_millis(400),
+            long_press_delay: Duration::from_millis(500),
+            drag_threshold: 8.0,
+        }
+    }
+}
+
+impl GestureConfig {
+    pub fn multi_click_interval(self, multi_click_interval: Duration) -> Self {
+        Self {
+            multi_click_interval,
+            ..self
+        }
+    }
+
+    pub fn long_press_delay(self, long_press_delay: Duration) -> Self {
+        Self {
+            long_press_delay,
+            ..self
+        }
+    }
+
+    pub fn drag_threshold(self, drag_threshold: f64) -> Self {
+        Self {
+            drag_threshold,
+            ..self
+        }
+    }
+}
+
+/// A gesture recognized from the pointer events of a seat. Positions are in buffer pixels.
+///
+/// The pointer is captured from a press to its release: the gestures of the press, and the drag
+/// it starts, are sent to the widget that received the press even when the pointer leaves it, so
+/// positions may lie outside the widget.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gesture {
+    /// A button was pressed and released without moving. `count` is 2 for a double click, 3 for
+    /// a triple click, and keeps counting for faster clicks.
+    Click {
+        x: f64,
+        y: f64,
+        button: u32,
+        count: u32,
+    },
+
+    /// A button was held without moving for the long press delay. Its release is not a click.
+    LongPress { x: f64, y: f64, button: u32 },
+
+    /// The pointer moved past the drag threshold while a button was held. The position is the one
+    /// of the press.
+    DragStarted { x: f64, y: f64, button: u32 },
+
+    /// The pointer moved during a drag, by `delta_x` and `delta_y` since the press.
+    DragMoved {
+        x: f64,
+        y: f64,
+        delta_x: f64,
+        delta_y: f64,
+    },
+
+    /// The button of the drag was released.
+    DragEnded { x: f64, y: f64, button: u32 },
+}
+
+/// A button held down.
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    button: u32,
+    origin: (f64, f64),
+    at: Instant,
+
+    dragging: bool,
+    long_pressed: bool,
+}
+
+/// A click, which the next one may follow to make a double click.
+#[derive(Debug, Clone, Copy)]
+struct LastClick {
+    button: u32,
+    position: (f64, f64),
+    at: Instant,
+    count: u32,
+}
+
+/// The pointer of a seat, tracked across its events.
+#[derive(Debug, Default, Clone, Copy)]
+struct PointerState {
+    press: Option<Press>,
+    last_click: Option<LastClick>,
+}
+
+/// Recognizes gestures from the pointer events of every seat. It is fed the events with
+/// `GestureRecognizer::handle`, and `GestureRecognizer::expire` must be called when its deadline
+/// is reached, to recognize long presses.
+#[derive(Debug, Default, Clone)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+
+    pointers: HashMap<SeatId, PointerState>,
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            pointers: HashMap::new(),
+        }
+    }
+
+    /// Whether a button of the seat is held, so its pointer is captured by the widget it was
+    /// pressed on. A widget made of several widgets should deliver the pointer events of the
+    /// seat to the child that received the press until then.
+    pub fn is_captured(&self, seat: SeatId) -> bool {
+        self.pointers
+            .get(&seat)
+            .is_some_and(|pointer| pointer.press.is_some())
+    }
+
+    /// Feeds a pointer event to the recognizer, and returns the gestures it completes as
+    /// `Event::Gesture`. The other events are ignored.
+    pub fn handle(&mut self, event: &Event, now: Instant) -> Vec<Event> {
+        let config = self.config;
+
+        let Some(seat) = event.seat() else {
+            return Vec::new();
+        };
+
+        let pointer = self.pointers.entry(seat).or_default();
+
+        let gestures = match *event {
+            Event::PointerPressed { x, y, button, .. } => {
+                // Other buttons pressed during a press are ignored.
+                if pointer.press.is_none() {
+                    pointer.press = Some(Press {
+                        button,
+                        origin: (x, y),
+                        at: now,
+                        dragging: false,
+                        long_pressed: false,
+                    });
+                }
+
+                Vec::new()
+            }
+            Event::PointerMoved { x, y, .. } => match &mut pointer.press {
+                Some(press) if press.dragging => vec![Gesture::DragMoved {
+                    x,
+                    y,
+                    delta_x: x - press.origin.0,
+                    delta_y: y - press.origin.1,
+                }],
+                Some(press) if distance(press.origin, (x, y)) > config.drag_threshold => {
+                    press.dragging = true;
+
+                    vec![
+                        Gesture::DragStarted {
+                            x: press.origin.0,
+                            y: press.origin.1,
+                            button: press.button,
+                        },
+                        Gesture::DragMoved {
+                            x,
+                            y,
+                            delta_x: x - press.origin.0,
+                            delta_y: y - press.origin.1,
+                        },
+                    ]
+                }
+                _ => Vec::new(),
+            },
+            Event::PointerReleased { x, y, button, .. } => {
+                match pointer.press.take_if(|press| press.button == button) {
+                    Some(press) if press.dragging => vec![Gesture::DragEnded { x, y, button }],
+                    Some(press) if press.long_pressed => Vec::new(),
+                    Some(_) => {
+                        let count = match pointer.last_click {
+                            Some(click)
+                                if click.button == button
+                                    && now.duration_since(click.at)
+                                        <= config.multi_click_interval
+                                    && distance(click.position, (x, y))
+                                        <= config.drag_threshold =>
+                            {
+                                click.count + 1
+                            }
+                            _ => 1,
+                        };
+
+                        pointer.last_click = Some(LastClick {
+                            button,
+                            position: (x, y),
+                            at: now,
+                            count,
+                        });
+
+                        vec![Gesture::Click {
+                            x,
+                            y,
+                            button,
+                            count,
+                        }]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            Event::PointerCancelled { .. } => {
+                pointer.press = None;
+
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+
+        gestures
+            .into_iter()
+            .map(|gesture| Event::Gesture { seat, gesture })
+            .collect()
+    }
+
+    /// The next time a long press may be recognized, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pointers
+            .values()
+            .filter_map(|pointer| pointer.press)
+            .filter(|press| !press.dragging && !press.long_pressed)
+            .map(|press| press.at + self.config.long_press_delay)
+            .min()
+    }
+
+    /// Recognizes the long presses whose delay elapsed, as `Event::Gesture`.
+    pub fn expire(&mut self, now: Instant) -> Vec<Event> {
+        let delay = self.config.long_press_delay;
+
+        let mut events = Vec::new();
+
+        for (seat, pointer) in &mut self.pointers {
+            let Some(press) = &mut pointer.press else {
+                continue;
+            };
+
+            if press.dragging || press.long_pressed || press.at + delay > now {
+                continue;
+            }
+
+            press.long_pressed = true;
+
+            events.push(Event::Gesture {
+                seat: *seat,
+                gesture: Gesture::LongPress {
+                    x: press.origin.0,
+                    y: press.origin.1,
+                    button: press.button,
+                },
+            });
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTN_LEFT: u32 = 0x110;
+
+    fn press(x: f64, y: f64) -> Event {
+        Event::PointerPressed {
+            seat: SeatId::default(),
+            x,
+            y,
+            button: BTN_LEFT,
+        }
+    }
+
+    fn release(x: f64, y: f64) -> Event {
+        Event::PointerReleased {
+            seat: SeatId::default(),
+            x,
+            y,
+            button: BTN_LEFT,
+        }
+    }
+
+    fn motion(x: f64, y: f64) -> Event {
+        Event::PointerMoved {
+            seat: SeatId::default(),
+            x,
+            y,
+        }
+    }
+
+    fn gestures(events: Vec<Event>) -> Vec<Gesture> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Gesture { gesture, .. } => Some(gesture),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_clicks() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+
+        for count in 1..=3 {
+            let at = now + Duration::from_millis(100 * count as u64);
+
+            recognizer.handle(&press(10.0, 10.0), at);
+
+            assert_eq!(
+                gestures(recognizer.handle(&release(10.0, 10.0), at)),
+                vec![Gesture::Click {
+                    x: 10.0,
+                    y: 10.0,
+                    button: BTN_LEFT,
+                    count,
+                }]
+            );
+        }
+
+        let later = now + Duration::from_secs(2);
+
+        recognizer.handle(&press(10.0, 10.0), later);
+
+        assert!(matches!(
+            gestures(recognizer.handle(&release(10.0, 10.0), later))[..],
+            [Gesture::Click { count: 1, .. }]
+        ));
+    }
+
+    #[test]
+    fn recognizes_drags() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+
+        recognizer.handle(&press(0.0, 0.0), now);
+
+        assert!(gestures(recognizer.handle(&motion(2.0, 0.0), now)).is_empty());
+        assert!(matches!(
+            gestures(recognizer.handle(&motion(-20.0, 0.0), now))[..],
+            [
+                Gesture::DragStarted { x: 0.0, .. },
+                Gesture::DragMoved { delta_x: -20.0, .. }
+            ]
+        ));
+        assert!(recognizer.is_captured(SeatId::default()));
+        assert!(matches!(
+            gestures(recognizer.handle(&release(-20.0, 0.0), now))[..],
+            [Gesture::DragEnded { .. }]
+        ));
+        assert!(!recognizer.is_captured(SeatId::default()));
+    }
+
+    #[test]
+    fn recognizes_long_presses() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+
+        recognizer.handle(&press(0.0, 0.0), now);
+
+        let deadline = recognizer.deadline().unwrap();
+
+        assert!(recognizer.expire(now).is_empty());
+        assert!(matches!(
+            gestures(recognizer.expire(deadline))[..],
+            [Gesture::LongPress { .. }]
+        ));
+        assert_eq!(recognizer.deadline(), None);
+        assert!(gestures(recognizer.handle(&release(0.0, 0.0), deadline)).is_empty());
+    }
+
+    #[test]
+    fn forgets_cancelled_presses() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+
+        recognizer.handle(&press(0.0, 0.0), now);
+        recognizer.handle(
+            &Event::PointerCancelled {
+                seat: SeatId::default(),
+            },
+            now,
+        );
+
+        assert!(!recognizer.is_captured(SeatId::default()));
+        assert_eq!(recognizer.deadline(), None);
+        assert!(gestures(recognizer.handle(&release(0.0, 0.0), now)).is_empty());
+    }
+}
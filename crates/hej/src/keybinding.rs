@@ -0,0 +1,649 @@
+//! This module defines `Keybindings`, a registry of keyboard shortcuts mapped to messages.
+//! Shortcuts are written like `Ctrl+Shift+P`, and a sequence of shortcuts is separated by spaces,
+//! like `Ctrl+K Ctrl+C`.
+
+use std::{collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
+
+use crate::prelude::*;
+
+/// A key pressed with a set of modifiers. Lock modifiers are ignored, and characters are
+/// compared in lowercase, so `Ctrl+Shift+P` matches whether the layout produced `p` or `P`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self {
+            modifiers: Modifiers {
+                caps_lock: false,
+                num_lock: false,
+                ..modifiers
+            },
+            key: match key {
+                Key::Character(c) => Key::Character(c.to_lowercase().next().unwrap_or(c)),
+                key => key,
+            },
+        }
+    }
+
+    /// The chord of a key press.
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self::new(event.modifiers, event.key)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = Modifiers::default();
+
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+
+        // `Ctrl++` binds the plus key.
+        if s.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+
+        let Some((key, parts)) = parts.split_last() else {
+            return Err(Report::msg(format!("Empty shortcut {:?}", s)));
+        };
+
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "super" | "logo" | "meta" => modifiers.logo = true,
+                _ => {
+                    return Err(Report::msg(format!(
+                        "Unknown modifier {:?} in {:?}",
+                        part, s
+                    )));
+                }
+            }
+        }
+
+        let named = match key.to_lowercase().as_str() {
+            "enter" | "return" => NamedKey::Enter,
+            "tab" => NamedKey::Tab,
+            "space" => NamedKey::Space,
+            "backspace" => NamedKey::Backspace,
+            "escape" | "esc" => NamedKey::Escape,
+            "delete" | "del" => NamedKey::Delete,
+            "insert" | "ins" => NamedKey::Insert,
+            "home" => NamedKey::Home,
+            "end" => NamedKey::End,
+            "pageup" => NamedKey::PageUp,
+            "pagedown" => NamedKey::PageDown,
+            "up" | "arrowup" => NamedKey::ArrowUp,
+            "down" | "arrowdown" => NamedKey::ArrowDown,
+            "left" | "arrowleft" => NamedKey::ArrowLeft,
+            "right" | "arrowright" => NamedKey::ArrowRight,
+            name => match (name.strip_prefix('f'), name.chars().count()) {
+                (_, 1) => {
+                    return Ok(Self::new(
+                        modifiers,
+                        Key::Character(name.chars().next().unwrap()),
+                    ));
+                }
+                (Some(n), _) if n.parse::<u8>().is_ok_and(|n| (1..=24).contains(&n)) => {
+                    NamedKey::F(n.parse().unwrap())
+                }
+                _ => return Err(Report::msg(format!("Unknown key {:?} in {:?}", key, s))),
+            },
+        };
+
+        Ok(Self::new(modifiers, Key::Named(named)))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.logo, "Super"),
+        ] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.key {
+            Key::Character(c) => write!(f, "{}", c.to_uppercase()),
+            Key::Named(NamedKey::F(n)) => write!(f, "F{}", n),
+            Key::Named(named) => write!(f, "{:?}", named),
+            Key::Unidentified(keysym) => write!(f, "{:#x}", keysym),
+        }
+    }
+}
+
+/// A sequence of chords pressed one after the other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    /// Whether this sequence starts with every chord of `other`.
+    pub fn starts_with(&self, other: &KeySequence) -> bool {
+        self.0.starts_with(&other.0)
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let chords = s
+            .split_whitespace()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>>>()?;
+
+        if chords.is_empty() {
+            return Err(Report::msg("Empty key sequence"));
+        }
+
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{}", chord)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Where a binding applies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Whichever surface has the keyboard focus.
+    Global,
+
+    /// Only when the surface with this label has the keyboard focus. These bindings take
+    /// precedence over the global ones.
+    Surface(String),
+}
+
+impl Scope {
+    fn contains(&self, focus: Option<&str>) -> bool {
+        match self {
+            Self::Global => true,
+            Self::Surface(label) => focus == Some(label.as_str()),
+        }
+    }
+
+    /// Whether both scopes apply to a same focused surface.
+    fn overlaps(&self, other: &Scope) -> bool {
+        self == other || *self == Self::Global || *other == Self::Global
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "the global scope"),
+            Self::Surface(label) => write!(f, "{}", label),
+        }
+    }
+}
+
+/// The outcome of a key press fed to `Keybindings::handle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyMatch<Message> {
+    /// The press is not part of a binding, it is delivered to the focused widget.
+    Unmatched,
+
+    /// The press starts or continues a sequence, which is still pending.
+    Pending,
+
+    /// The press completed a binding, which sent this message.
+    Completed(Message),
+}
+
+impl<Message> KeyMatch<Message> {
+    /// Whether the press is part of a binding, and must not be delivered to the focused widget.
+    pub fn is_consumed(&self) -> bool {
+        !matches!(self, Self::Unmatched)
+    }
+
+    /// The message of the completed binding, if any.
+    pub fn message(self) -> Option<Message> {
+        match self {
+            Self::Completed(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+struct Binding<Message> {
+    scope: Scope,
+    sequence: KeySequence,
+    message: Arc<dyn Fn() -> Message + Send + Sync>,
+}
+
+/// A registry of keyboard shortcuts mapped to messages. An application registers its bindings
+/// with `Application::keybindings`, and a widget keeps its own, which apply while it has the
+/// focus, and feeds them its presses in `Widget::on_shortcut`.
+/// Example usage:
+///
+/// ```rust
+/// use hej::prelude::*;
+///
+/// enum Message {
+///     Palette,
+///     Comment,
+/// }
+///
+/// fn bindings() -> Result<Keybindings<Message>> {
+///     Keybindings::new()
+///         .bind("Ctrl+Shift+P", || Message::Palette)?
+///         .bind_in("editor", "Ctrl+K Ctrl+C", || Message::Comment)
+/// }
+/// ```
+pub struct Keybindings<Message> {
+    bindings: Vec<Binding<Message>>,
+
+    /// The chords of a sequence pressed so far on each seat, so that sequences typed at the same
+    /// time on different seats do not mix.
+    pending: HashMap<SeatId, Vec<KeyChord>>,
+}
+
+impl<Message> Default for Keybindings<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Message> Keybindings<Message> {
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Binds a sequence in the global scope.
+    pub fn bind(
+        self,
+        sequence: &str,
+        message: impl Fn() -> Message + 'static + Send + Sync,
+    ) -> Result<Self> {
+        self.bind_scoped(Scope::Global, sequence.parse()?, Arc::new(message))
+    }
+
+    /// Binds a sequence in the scope of the surface with the given label.
+    pub fn bind_in(
+        self,
+        label: impl Into<String>,
+        sequence: &str,
+        message: impl Fn() -> Message + 'static + Send + Sync,
+    ) -> Result<Self> {
+        self.bind_scoped(
+            Scope::Surface(label.into()),
+            sequence.parse()?,
+            Arc::new(message),
+        )
+    }
+
+    /// Binds a sequence in a scope. A sequence conflicts with the sequences of the same scope that
+    /// are equal to it or start with it, since one of them could never be triggered. It also
+    /// conflicts with the longer or shorter sequences of the global scope, or of any surface when
+    /// it is global, while an equal sequence of a surface overrides the global one.
+    pub fn bind_scoped(
+        mut self,
+        scope: Scope,
+        sequence: KeySequence,
+        message: Arc<dyn Fn() -> Message + Send + Sync>,
+    ) -> Result<Self> {
+        if let Some(binding) = self.bindings.iter().find(|binding| {
+            binding.scope.overlaps(&scope)
+                && (binding.scope == scope || binding.sequence != sequence)
+                && (binding.sequence.starts_with(&sequence)
+                    || sequence.starts_with(&binding.sequence))
+        }) {
+            return Err(Report::msg(format!(
+                "{} conflicts with {} in {}",
+                sequence, binding.sequence, scope
+            )));
+        }
+
+        self.bindings.push(Binding {
+            scope,
+            sequence,
+            message,
+        });
+
+        Ok(self)
+    }
+
+    /// Loads bindings from a file, see `Keybindings::parse`.
+    pub fn load(
+        self,
+        path: impl AsRef<Path>,
+        actions: impl Fn(&str) -> Option<Message> + 'static + Send + Sync,
+    ) -> Result<Self> {
+        self.parse(&std::fs::read_to_string(path)?, actions)
+    }
+
+    /// Parses bindings from a configuration. Each line binds a sequence to an action name, which
+    /// `actions` maps to a message. Bindings following a `[label]` header are scoped to the
+    /// surface with this label, and lines starting with `#` are comments.
+    ///
+    /// ```text
+    /// Ctrl+Shift+P = palette
+    ///
+    /// [editor]
+    /// Ctrl+K Ctrl+C = comment
+    /// ```
+    pub fn parse(
+        mut self,
+        config: &str,
+        actions: impl Fn(&str) -> Option<Message> + 'static + Send + Sync,
+    ) -> Result<Self> {
+        let actions = Arc::new(actions);
+
+        let mut scope = Scope::Global;
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(label) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                scope = Scope::Surface(label.trim().to_string());
+
+                continue;
+            }
+
+            let Some((sequence, action)) = line.rsplit_once('=') else {
+                return Err(Report::msg(format!(
+                    "Expected `sequence = action` on line {}",
+                    number + 1
+                )));
+            };
+
+            let action = action.trim().to_string();
+
+            if actions(&action).is_none() {
+                return Err(Report::msg(format!(
+                    "Unknown action {:?} on line {}",
+                    action,
+                    number + 1
+                )));
+            }
+
+            let actions = actions.clone();
+
+            self = self.bind_scoped(
+                scope.clone(),
+                sequence.trim().parse()?,
+                Arc::new(move || actions(&action).expect("The action was checked when loaded")),
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    /// Feeds a key press to the registry, `focus` being the label of the focused surface.
+    /// Returns whether the press completed a binding, with its message, or is part of a pending
+    /// sequence. Presses of modifiers alone and repeats are not matched.
+    pub fn handle(&mut self, focus: Option<&str>, event: &KeyEvent) -> KeyMatch<Message> {
+        if event.repeat || matches!(event.key, Key::Named(named) if named.is_modifier()) {
+            return KeyMatch::Unmatched;
+        }
+
+        let mut pending = self.pending.remove(&event.seat).unwrap_or_default();
+
+        pending.push(KeyChord::from_event(event));
+
+        // The sequence cannot be completed anymore, but its last chord may start another one.
+        if pending.len() > 1 && !self.is_pending(focus, &pending) {
+            pending.drain(..pending.len() - 1);
+        }
+
+        let matched = self.complete(focus, &mut pending);
+
+        if !pending.is_empty() {
+            self.pending.insert(event.seat, pending);
+        }
+
+        matched
+    }
+
+    /// Whether the pending chords start a binding of the focused surface or the global scope.
+    fn is_pending(&self, focus: Option<&str>, pending: &[KeyChord]) -> bool {
+        let pending = KeySequence(pending.to_vec());
+
+        self.bindings
+            .iter()
+            .any(|binding| binding.scope.contains(focus) && binding.sequence.starts_with(&pending))
+    }
+
+    /// Triggers the binding matching the pending chords, or keeps them if they start a binding.
+    fn complete(&self, focus: Option<&str>, pending: &mut Vec<KeyChord>) -> KeyMatch<Message> {
+        let sequence = KeySequence(pending.clone());
+
+        // Bindings of the focused surface take precedence over the global ones.
+        if let Some(binding) = self
+            .bindings
+            .iter()
+            .filter(|binding| binding.scope.contains(focus) && binding.sequence == sequence)
+            .min_by_key(|binding| binding.scope == Scope::Global)
+        {
+            pending.clear();
+
+            return KeyMatch::Completed((binding.message)());
+        }
+
+        if !self.is_pending(focus, pending) {
+            pending.clear();
+
+            return KeyMatch::Unmatched;
+        }
+
+        KeyMatch::Pending
+    }
+}
+
+impl<Message: 'static + Send + Sync> Keybindings<Message> {
+    /// Feeds a key press to the bindings of a widget and submits the message of a completed
+    /// binding. Only the bindings of the global scope apply, the widget being focused. Returns
+    /// whether the press is consumed, see `Widget::on_shortcut`.
+    pub fn submit(&mut self, event: &KeyEvent, client: &Submitter<Message>) -> Result<bool> {
+        let matched = self.handle(None, event);
+        let consumed = matched.is_consumed();
+
+        if let Some(message) = matched.message() {
+            client.submit(message)?;
+        }
+
+        Ok(consumed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(modifiers: Modifiers, key: Key) -> KeyEvent {
+        KeyEvent {
+            seat: SeatId::default(),
+            key,
+            code: 0,
+            text: None,
+            modifiers,
+            repeat: false,
+        }
+    }
+
+    fn ctrl() -> Modifiers {
+        Modifiers {
+            ctrl: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = "Ctrl+Shift+P".parse::<KeyChord>().unwrap();
+
+        assert!(chord.modifiers.ctrl && chord.modifiers.shift);
+        assert_eq!(chord.key, Key::Character('p'));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+P");
+
+        assert_eq!(
+            "Alt+F4".parse::<KeyChord>().unwrap().key,
+            Key::Named(NamedKey::F(4))
+        );
+        assert_eq!(
+            "Ctrl++".parse::<KeyChord>().unwrap().key,
+            Key::Character('+')
+        );
+        assert!("Hyper+A".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn matches_sequences() {
+        let mut bindings = Keybindings::new()
+            .bind("Ctrl+K Ctrl+C", || 1)
+            .unwrap()
+            .bind("Ctrl+S", || 2)
+            .unwrap();
+
+        let k = press(ctrl(), Key::Character('k'));
+        let c = press(ctrl(), Key::Character('c'));
+        let s = press(ctrl(), Key::Character('s'));
+
+        assert_eq!(bindings.handle(None, &k), KeyMatch::Pending);
+        assert_eq!(bindings.handle(None, &c), KeyMatch::Completed(1));
+
+        // A broken sequence does not swallow the chord that breaks it.
+        assert_eq!(bindings.handle(None, &k), KeyMatch::Pending);
+        assert_eq!(bindings.handle(None, &s), KeyMatch::Completed(2));
+
+        let a = press(Modifiers::default(), Key::Character('a'));
+
+        assert!(!bindings.handle(None, &a).is_consumed());
+    }
+
+    #[test]
+    fn separates_seats() {
+        let mut bindings = Keybindings::new().bind("Ctrl+K Ctrl+C", || 1).unwrap();
+
+        let k = press(ctrl(), Key::Character('k'));
+        let c = KeyEvent {
+            seat: SeatId(1),
+            ..press(ctrl(), Key::Character('c'))
+        };
+
+        assert_eq!(bindings.handle(None, &k), KeyMatch::Pending);
+        assert_eq!(bindings.handle(None, &c), KeyMatch::Unmatched);
+        assert_eq!(
+            bindings.handle(
+                None,
+                &KeyEvent {
+                    seat: SeatId(0),
+                    ..c
+                }
+            ),
+            KeyMatch::Completed(1)
+        );
+    }
+
+    #[test]
+    fn scopes_bindings() {
+        let mut bindings = Keybindings::new()
+            .bind("Ctrl+S", || 1)
+            .unwrap()
+            .bind_in("editor", "Ctrl+S", || 2)
+            .unwrap();
+
+        let s = press(ctrl(), Key::Character('S'));
+
+        assert_eq!(bindings.handle(Some("bar"), &s), KeyMatch::Completed(1));
+        assert_eq!(bindings.handle(Some("editor"), &s), KeyMatch::Completed(2));
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let bindings = Keybindings::new().bind("Ctrl+K", || 1).unwrap();
+
+        assert!(bindings.bind("Ctrl+K Ctrl+C", || 2).is_err());
+    }
+
+    #[test]
+    fn detects_conflicts_across_scopes() {
+        let bindings = Keybindings::new().bind("Ctrl+K", || 1).unwrap();
+
+        let bindings = bindings.bind_in("editor", "Ctrl+K Ctrl+C", || 2);
+        assert!(bindings.is_err());
+
+        let bindings = Keybindings::new()
+            .bind_in("editor", "Ctrl+K Ctrl+C", || 1)
+            .unwrap()
+            .bind_in("bar", "Ctrl+K", || 2)
+            .unwrap();
+
+        assert!(bindings.bind("Ctrl+K", || 3).is_err());
+    }
+
+    #[test]
+    fn parses_configs() {
+        let mut bindings = Keybindings::new()
+            .parse(
+                "# Shortcuts\nCtrl+S = save\n\n[editor]\nCtrl+K Ctrl+C = comment\n",
+                |action| match action {
+                    "save" => Some(1),
+                    "comment" => Some(2),
+                    _ => None,
+                },
+            )
+            .unwrap();
+
+        let s = press(ctrl(), Key::Character('s'));
+
+        assert_eq!(bindings.handle(None, &s), KeyMatch::Completed(1));
+
+        assert!(
+            Keybindings::new()
+                .parse("Ctrl+S = unknown", |_| None::<u32>)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn submits_widget_bindings() {
+        let mut bindings = Keybindings::new().bind("Ctrl+K Ctrl+C", || 1).unwrap();
+        let (client, mut server) = channel();
+
+        let k = press(ctrl(), Key::Character('k'));
+        let c = press(ctrl(), Key::Character('c'));
+        let a = press(Modifiers::default(), Key::Character('a'));
+
+        assert!(bindings.submit(&k, &client).unwrap());
+        assert!(server.try_recv().is_err());
+
+        assert!(bindings.submit(&c, &client).unwrap());
+        assert_eq!(server.try_recv().unwrap(), 1);
+
+        assert!(!bindings.submit(&a, &client).unwrap());
+    }
+}
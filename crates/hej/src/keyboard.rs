@@ -0,0 +1,92 @@
+//! This module defines the key model of keyboard events: the logical `Key` produced by the
+//! keyboard layout, the `Modifiers` held, and the `KeyEvent` sent to widgets.
+
+use crate::prelude::*;
+
+/// The modifiers active when a key event occurred.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub caps_lock: bool,
+    pub logo: bool,
+    pub num_lock: bool,
+}
+
+/// A key that does not produce a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedKey {
+    Enter,
+    Tab,
+    Space,
+    Backspace,
+    Escape,
+    Delete,
+    Insert,
+
+    Home,
+    End,
+    PageUp,
+    PageDown,
+
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+
+    Shift,
+    Control,
+    Alt,
+    Super,
+    CapsLock,
+    NumLock,
+
+    /// A function key, from `F(1)` to `F(24)`.
+    F(u8),
+}
+
+impl NamedKey {
+    /// Whether the key is a modifier, which is never repeated.
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            Self::Shift | Self::Control | Self::Alt | Self::Super | Self::CapsLock | Self::NumLock
+        )
+    }
+}
+
+/// The logical key of a key event, which depends on the keyboard layout and the modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Named(NamedKey),
+
+    /// A key producing a character, e.g. `'a'`, or `'A'` with shift held.
+    Character(char),
+
+    /// A key with no known meaning, holding its raw keysym.
+    Unidentified(u32),
+}
+
+/// A key press or release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEvent {
+    /// The seat of the keyboard.
+    pub seat: SeatId,
+
+    pub key: Key,
+
+    /// The code of the physical key, independent of the keyboard layout.
+    pub code: u32,
+
+    /// The text produced by a key press. It is `None` for releases and for keys that do not
+    /// produce text. Backends that compose text, like kyo with the compose table of the locale,
+    /// also leave it `None` while a compose sequence or a dead key is in progress: the composed
+    /// text is produced by the press that completes it.
+    pub text: Option<String>,
+
+    pub modifiers: Modifiers,
+
+    /// Whether the press was synthesized because the key is held down.
+    pub repeat: bool,
+}
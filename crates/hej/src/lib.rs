@@ -1,4 +1,11 @@
+pub mod clipboard;
+pub mod cursor;
 pub mod event;
+pub mod gesture;
+pub mod keybinding;
+pub mod keyboard;
+pub mod text_input;
+pub mod window;
 
 pub mod map;
 
@@ -18,7 +25,14 @@ pub mod prelude {
     //! Note: You may need to also import the `reexport` module for additional types when
     //! dealing with widgets.
 
+    pub use crate::clipboard::*;
+    pub use crate::cursor::*;
     pub use crate::event::*;
+    pub use crate::gesture::*;
+    pub use crate::keybinding::*;
+    pub use crate::keyboard::*;
+    pub use crate::text_input::*;
+    pub use crate::window::*;
     pub use eyre::{Report, Result};
 
     pub use crate::map::*;
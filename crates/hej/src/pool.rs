@@ -48,6 +48,15 @@ impl<Message: Sync + Send + 'static> TaskPool<Message> {
                                         ApplicationDirective::Submit(element)
                                     }
                                     SpecialTask::Close(label) => ApplicationDirective::Close(label),
+                                    SpecialTask::Redraw(label) => {
+                                        ApplicationDirective::Redraw(label)
+                                    }
+                                    SpecialTask::Window(label, request) => {
+                                        ApplicationDirective::Window(label, request)
+                                    }
+                                    SpecialTask::Clipboard(request) => {
+                                        ApplicationDirective::Clipboard(request)
+                                    }
                                     SpecialTask::None => unreachable!(),
                                 })
                                 .unwrap_or_else(|e| {
@@ -14,6 +14,9 @@ pub(crate) enum SpecialTask<Message> {
 
     Submit(Element<Message>),
     Close(String),
+    Redraw(String),
+    Window(String, WindowRequest),
+    Clipboard(ClipboardRequest),
 }
 
 pub(crate) enum TaskHandle<Message> {
@@ -161,6 +164,86 @@ impl<Message: Sync + Send + 'static> Task<Message> {
         }
     }
 
+    /// Creates a special task that asks the backend to redraw the element with the given label
+    /// on its next frame.
+    pub fn redraw(label: impl Into<String>) -> Self {
+        Task {
+            handle: TaskHandle::Special(SpecialTask::Redraw(label.into())),
+            signal: None,
+        }
+    }
+
+    /// Creates a special task that sends a request about the window with the given label to the
+    /// compositor.
+    pub fn window(label: impl Into<String>, request: WindowRequest) -> Self {
+        Task {
+            handle: TaskHandle::Special(SpecialTask::Window(label.into(), request)),
+            signal: None,
+        }
+    }
+
+    /// Creates a task that reads the clipboard in a MIME type, e.g. `text/plain;charset=utf-8`,
+    /// and resolves with the message made of the data. It fails when the clipboard is empty or not
+    /// offered in this MIME type.
+    /// Example:
+    /// ```rust
+    /// use hej::prelude::{reexport::*, *};
+    ///
+    /// enum Message {
+    ///     Paste(String),
+    /// }
+    ///
+    /// let task = Task::read_clipboard("text/plain;charset=utf-8", |data| {
+    ///     Message::Paste(String::from_utf8_lossy(&data).into_owned())
+    /// });
+    /// ```
+    pub fn read_clipboard(
+        mime_type: impl Into<String>,
+        on_read: impl FnOnce(Vec<u8>) -> Message + 'static + Send + Sync,
+    ) -> Self {
+        Self::read_selection(Selection::Clipboard, mime_type, on_read)
+    }
+
+    /// Creates a task that reads a selection in a MIME type, like `Task::read_clipboard`.
+    pub fn read_selection(
+        selection: Selection,
+        mime_type: impl Into<String>,
+        on_read: impl FnOnce(Vec<u8>) -> Message + 'static + Send + Sync,
+    ) -> Self {
+        let (reply, data) = tokio::sync::oneshot::channel();
+
+        let request = Task {
+            handle: TaskHandle::Special(SpecialTask::Clipboard(ClipboardRequest::Read {
+                selection,
+                mime_type: mime_type.into(),
+                reply,
+            })),
+            signal: None,
+        };
+
+        request.then(Task::new(async move {
+            let data = data.await.map_err(Report::msg)??;
+
+            Ok(on_read(data))
+        }))
+    }
+
+    /// Creates a special task that puts data on the clipboard.
+    pub fn write_clipboard(data: ClipboardData) -> Self {
+        Self::write_selection(Selection::Clipboard, data)
+    }
+
+    /// Creates a special task that puts data on a selection.
+    pub fn write_selection(selection: Selection, data: ClipboardData) -> Self {
+        Task {
+            handle: TaskHandle::Special(SpecialTask::Clipboard(ClipboardRequest::Write {
+                selection,
+                data,
+            })),
+            signal: None,
+        }
+    }
+
     /// Maps this Task<Message> to another Task<NewMessage>
     pub fn map<NewMessage: 'static + Send + Sync>(
         self,
@@ -190,6 +273,13 @@ impl<Message: Sync + Send + 'static> Task<Message> {
                         TaskHandle::Special(SpecialTask::Submit(element.map(map.clone())))
                     }
                     SpecialTask::Close(label) => TaskHandle::Special(SpecialTask::Close(label)),
+                    SpecialTask::Redraw(label) => TaskHandle::Special(SpecialTask::Redraw(label)),
+                    SpecialTask::Window(label, request) => {
+                        TaskHandle::Special(SpecialTask::Window(label, request))
+                    }
+                    SpecialTask::Clipboard(request) => {
+                        TaskHandle::Special(SpecialTask::Clipboard(request))
+                    }
                 },
             },
             signal: None,
@@ -0,0 +1,20 @@
+//! This module defines `TextInput`, the state of a text field that an input method needs to
+//! compose text, e.g. Chinese or Japanese.
+
+use crate::prelude::*;
+
+/// The state of the text field of a widget. It is returned by `Widget::text_input`, and the
+/// input method answers with `Event::Preedit`, `Event::Commit` and `Event::DeleteSurrounding`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextInput {
+    /// The text around the cursor, usually the paragraph being edited.
+    pub surrounding: String,
+
+    /// The byte offset of the cursor in `surrounding`.
+    pub cursor: usize,
+    /// The byte offset of the other end of the selection, equal to `cursor` without a selection.
+    pub anchor: usize,
+
+    /// The rectangle of the cursor in buffer pixels, next to which the candidates are shown.
+    pub cursor_rect: Rect,
+}
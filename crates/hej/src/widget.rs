@@ -3,7 +3,7 @@
 //! quickly and return a `Result<()>`.
 //! The trait also provides methods for type conversion to `Any`, allowing for dynamic type handling.
 
-use std::any::Any;
+use std::{any::Any, sync::Arc};
 
 use crate::prelude::*;
 
@@ -19,12 +19,60 @@ pub trait Widget<Message>: Send + Sync + Any {
         Ok(())
     }
 
+    /// This function is called with the key presses of the widget's surface before they are
+    /// matched against the keybindings of the application, so that the shortcuts of the widget
+    /// take precedence while it has the focus. It returns whether the press is consumed, it is
+    /// then not sent as an `Event::KeyPressed`. A widget usually keeps its own `Keybindings` and
+    /// feeds them with `Keybindings::submit`.
+    #[allow(unused_variables)]
+    fn on_shortcut(&mut self, event: &KeyEvent, client: Submitter<Message>) -> Result<bool> {
+        Ok(false)
+    }
+
     /// This function is called to draw the widget using the provided renderer on the provided canvas.
     #[allow(unused_variables)]
     fn draw(&self, canvas: Canvas, renderer: &mut Renderer) -> Result<()> {
         Ok(())
     }
 
+    /// This function returns the regions of the widget that changed since the last call, and
+    /// clears them. The backend calls it after each event to decide what must be redrawn.
+    fn damage(&mut self) -> Damage {
+        Damage::None
+    }
+
+    /// This function returns the state of the text field of the widget, when it accepts text
+    /// input. The backend enables the input method while the widget's surface is focused and it
+    /// returns `Some`.
+    fn text_input(&self) -> Option<TextInput> {
+        None
+    }
+
+    /// This function returns whether the widget handles touch events. Otherwise, the backend
+    /// emulates a pointer with the first touch point, pressing the left button.
+    fn accepts_touch(&self) -> bool {
+        false
+    }
+
+    /// This function returns the cursor to show while the pointer is over the widget. It is
+    /// queried after every pointer event, so it may depend on the position of the pointer.
+    fn cursor(&self) -> Cursor {
+        Cursor::Default
+    }
+
+    /// This function returns the interactive operation the widget starts on its window, and
+    /// clears it. The backend calls it after each press, whose serial the compositor requires,
+    /// so the widget must ask for it while handling the press.
+    fn window_action(&mut self) -> Option<WindowAction> {
+        None
+    }
+
+    /// This function returns whether the widget handles the requests to close its window, which
+    /// then stays open until the application closes it. Otherwise, the backend closes it at once.
+    fn handles_close(&self) -> bool {
+        false
+    }
+
     fn layout(&self) -> Layout {
         Layout::default()
     }
@@ -120,10 +168,129 @@ impl<MessageA: 'static + Send + Sync, MessageB: 'static + Send + Sync> Widget<Me
         Ok(())
     }
 
+    fn on_shortcut(&mut self, event: &KeyEvent, client: Submitter<MessageB>) -> Result<bool> {
+        let (sender, mut receiver) = channel::<MessageA>();
+
+        let consumed = self.widget.on_shortcut(event, sender)?;
+
+        while let Ok(message) = receiver.try_recv() {
+            client.submit(self.map.map(message)).unwrap_or_else(|_| {
+                tracing::error!("Failed to send message from MapWidget");
+            });
+        }
+
+        Ok(consumed)
+    }
+
+    fn draw(&self, canvas: Canvas, renderer: &mut Renderer) -> Result<()> {
+        self.widget.draw(canvas, renderer)
+    }
+
+    fn damage(&mut self) -> Damage {
+        self.widget.damage()
+    }
+
+    fn text_input(&self) -> Option<TextInput> {
+        self.widget.text_input()
+    }
+
+    fn accepts_touch(&self) -> bool {
+        self.widget.accepts_touch()
+    }
+
+    fn cursor(&self) -> Cursor {
+        self.widget.cursor()
+    }
+
+    fn window_action(&mut self) -> Option<WindowAction> {
+        self.widget.window_action()
+    }
+
+    fn handles_close(&self) -> bool {
+        self.widget.handles_close()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// A widget that sends a message when its surface is asked to close. A window is then kept
+/// open, so the application may confirm or refuse, and closes it by removing it from its view
+/// or with `Task::close`.
+pub struct OnCloseWidget<Message> {
+    widget: Box<dyn Widget<Message>>,
+    on_close: Arc<dyn Fn() -> Message + Send + Sync>,
+}
+
+impl<Message> OnCloseWidget<Message> {
+    pub fn new(
+        widget: Box<dyn Widget<Message>>,
+        on_close: Arc<dyn Fn() -> Message + Send + Sync>,
+    ) -> Self {
+        Self { widget, on_close }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Widget<Message> for OnCloseWidget<Message> {
+    fn layout(&self) -> Layout {
+        self.widget.layout()
+    }
+
+    fn label(&self) -> Option<String> {
+        self.widget.label()
+    }
+
+    fn on_event(&mut self, event: Event, client: Submitter<Message>) -> Result<()> {
+        if event == Event::Close {
+            client.submit((self.on_close)()).unwrap_or_else(|_| {
+                tracing::error!("Failed to send message from OnCloseWidget");
+            });
+        }
+
+        self.widget.on_event(event, client)
+    }
+
+    fn on_shortcut(&mut self, event: &KeyEvent, client: Submitter<Message>) -> Result<bool> {
+        self.widget.on_shortcut(event, client)
+    }
+
     fn draw(&self, canvas: Canvas, renderer: &mut Renderer) -> Result<()> {
         self.widget.draw(canvas, renderer)
     }
 
+    fn damage(&mut self) -> Damage {
+        self.widget.damage()
+    }
+
+    fn text_input(&self) -> Option<TextInput> {
+        self.widget.text_input()
+    }
+
+    fn accepts_touch(&self) -> bool {
+        self.widget.accepts_touch()
+    }
+
+    fn cursor(&self) -> Cursor {
+        self.widget.cursor()
+    }
+
+    fn window_action(&mut self) -> Option<WindowAction> {
+        self.widget.window_action()
+    }
+
+    fn handles_close(&self) -> bool {
+        true
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -175,3 +342,69 @@ pub fn container<Message>() -> ContainerWidget<Message> {
         elements: Vec::new(),
     }
 }
+
+/// Builds the instance of a per output widget for an output.
+pub(crate) type OutputBuilder<Message> = Arc<dyn Fn(&Output) -> Element<Message> + Send + Sync>;
+
+/// A widget that is instantiated once per output by the backend. The builder is called with the
+/// description of each output, when the widget is submitted and whenever an output is plugged in,
+/// and the instances are destroyed with their output.
+pub struct PerOutputWidget<Message> {
+    pub(crate) label: Option<String>,
+    pub(crate) builder: OutputBuilder<Message>,
+}
+
+impl<Message: 'static + Send + Sync> PerOutputWidget<Message> {
+    pub fn label(self, label: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    /// Builds the instance of the widget for the given output.
+    pub fn build(&self, output: &Output) -> Element<Message> {
+        (self.builder)(output)
+    }
+
+    /// Maps the instances built by this widget to another message type.
+    pub fn map<NewMessage: 'static + Send + Sync>(
+        self,
+        map: Map<Message, NewMessage>,
+    ) -> PerOutputWidget<NewMessage> {
+        let builder = self.builder;
+
+        PerOutputWidget {
+            label: self.label,
+            builder: Arc::new(move |output: &Output| builder(output).map(map.clone())),
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Widget<Message> for PerOutputWidget<Message> {
+    fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Makes a 'PerOutputWidget'.
+pub fn per_output<Message>(
+    builder: impl Fn(&Output) -> Element<Message> + 'static + Send + Sync,
+) -> PerOutputWidget<Message> {
+    PerOutputWidget {
+        label: None,
+        builder: Arc::new(builder),
+    }
+}
@@ -0,0 +1,77 @@
+//! This module defines the state of a window as reported by the compositor, the requests an
+//! application makes about its windows with `Task::window`, and the interactive operations a
+//! widget starts with `Widget::window_action`.
+
+/// The edges of a window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edges {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+/// The state of a window, sent with every configure of the compositor as
+/// `ApplicationEvent::WindowConfigured`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WindowState {
+    /// The window has the focus, its decorations should be drawn as such.
+    pub activated: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+
+    /// The window is being resized by the user.
+    pub resizing: bool,
+
+    /// The window is hidden, e.g. on another workspace, so it should not animate.
+    pub suspended: bool,
+
+    /// The edges of the window placed against other windows or the output, e.g. by a tiling
+    /// layout. They should not be rounded nor shadowed.
+    pub tiled: Edges,
+
+    /// The largest size the window should take, e.g. the output without its panels, in logical
+    /// pixels.
+    pub bounds: Option<(u32, u32)>,
+}
+
+/// An interactive operation on a window, started by a widget in response to a press, e.g. on a
+/// titlebar or a resize handle it draws itself. The compositor then drives it until the button is
+/// released.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowAction {
+    /// Moves the window with the pointer.
+    Move,
+
+    /// Resizes the window from these edges, one edge or two adjacent ones for a corner.
+    Resize(Edges),
+
+    /// Shows the menu of the window, e.g. with maximize and close entries, at a position in buffer
+    /// pixels.
+    ShowMenu { x: f64, y: f64 },
+}
+
+/// A request about a window, sent with `Task::window`. The compositor may ignore it, the state
+/// it grants comes back as `ApplicationEvent::WindowConfigured`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowRequest {
+    SetTitle(String),
+    SetAppId(String),
+
+    /// The size limits of the window, in logical pixels, unbounded when `None`.
+    SetMinSize(Option<(u32, u32)>),
+    SetMaxSize(Option<(u32, u32)>),
+
+    /// The label of the window this one belongs to, e.g. for a dialog, none when `None`.
+    SetParent(Option<String>),
+
+    Maximize,
+    Unmaximize,
+
+    /// Shows the window fullscreen on the output with this name, the compositor's choice when
+    /// `None`.
+    Fullscreen(Option<String>),
+    Unfullscreen,
+
+    Minimize,
+}
@@ -44,8 +44,8 @@ impl State {
             }
             Message::Stop => Task::stop(),
             Message::Open => Task::submit(empty().label("bar.left").layout(Layout {
-                width: 24,
-                height: 1080,
+                width: Length::Fixed(24),
+                height: Length::Fill,
                 placement: Placement::Left,
 
                 ..Default::default()
@@ -57,8 +57,8 @@ impl State {
 
     fn render(&self) -> Element<Message> {
         let elements = container().with(empty().label("bar.bottom").layout(Layout {
-            width: 1920,
-            height: 24,
+            width: Length::Fill,
+            height: Length::Fixed(24),
             placement: Placement::Bottom,
 
             ..Default::default()
@@ -66,8 +66,8 @@ impl State {
 
         match self.top {
             true => elements.with(empty().label("bar.top").layout(Layout {
-                width: 1920,
-                height: 24,
+                width: Length::Fill,
+                height: Length::Fixed(24),
                 placement: Placement::Top,
 
                 ..Default::default()
@@ -33,10 +33,9 @@ impl State {
         empty()
             .label("menu")
             .layout(Layout {
-                width: 720,
-                height: 480,
-                x: 1920 / 2 - 720 / 2,
-                y: 1080 / 2 - 480 / 2,
+                width: Length::Percent(40.0),
+                height: Length::Percent(50.0),
+                anchor: Some(Anchor::CENTER),
                 keyboard_sensitivity: KeyboardSensitivity::OnClick,
                 ..Default::default()
             })
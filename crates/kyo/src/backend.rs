@@ -1,33 +1,60 @@
-use std::{pin::Pin, time::Duration};
+use std::{io::ErrorKind, os::fd::AsRawFd, pin::Pin};
+
+use eyre::OptionExt;
 
 use crate::prelude::*;
 
 pub(crate) mod wayland;
-use eyre::OptionExt;
 pub(crate) use wayland::*;
 
 use smithay_client_toolkit::{
     compositor::CompositorState,
+    reexports::protocols::wp::{
+        fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+        viewporter::client::wp_viewporter::WpViewporter,
+    },
     shell::{
         WaylandSurface,
-        wlr_layer::{Anchor, KeyboardInteractivity, Layer, LayerShell, LayerSurface},
+        wlr_layer::{self, KeyboardInteractivity, LayerShell, LayerSurface},
         xdg::{
-            XdgShell,
+            self, XdgPositioner, XdgShell, XdgSurface,
             window::{Window, WindowDecorations},
         },
     },
+    shm::Shm,
+    subcompositor::SubcompositorState,
 };
 
-use wayland_client::{Connection, EventQueue, globals::registry_queue_init};
-use wgpu::{Adapter, Device, Instance, PowerPreference, Queue, RequestAdapterOptions};
+use tokio::{
+    io::{Interest, unix::AsyncFd},
+    time::Instant,
+};
+use wayland_backend::client::WaylandError;
+use wayland_client::{
+    Connection, EventQueue, Proxy, globals::registry_queue_init, protocol::wl_output::WlOutput,
+};
+use wgpu::{
+    Adapter, Device, DeviceDescriptor, Instance, InstanceDescriptor, Queue, RequestAdapterOptions,
+};
 
 pub struct WaylandBackend<Message> {
+    pub(crate) config: WaylandConfig,
+
     pub(crate) submitter: Submitter<Element<Message>>,
     pub(crate) server: Server<Element<Message>>,
 
     pub(crate) closer: Submitter<String>,
     pub(crate) closer_server: Server<String>,
 
+    pub(crate) redrawer: Submitter<String>,
+    pub(crate) redrawer_server: Server<String>,
+
+    pub(crate) windower: Submitter<(String, WindowRequest)>,
+    pub(crate) windower_server: Server<(String, WindowRequest)>,
+
+    pub(crate) clipboard: Submitter<ClipboardRequest>,
+    pub(crate) clipboard_server: Server<ClipboardRequest>,
+
     // It is important to first destroy state, then the wgpu primitives, then the wayland primitives
     // At some point I should move to a ManuallyDrop struct
     pub(crate) state: State<Message>,
@@ -41,47 +68,99 @@ pub struct WaylandBackend<Message> {
     pub(crate) compositor_state: CompositorState,
     pub(crate) xdg_shell: XdgShell,
     pub(crate) layer_shell: LayerShell,
+    pub(crate) viewporter: Option<WpViewporter>,
+    pub(crate) fractional_scale_manager: Option<WpFractionalScaleManagerV1>,
     pub(crate) connection: Connection,
 }
 
 impl<Message: 'static + Send + Sync> Backend<Message> for WaylandBackend<Message> {
-    async fn new(msg_submitter: Submitter<Message>) -> Result<Self> {
+    type Config = WaylandConfig;
+
+    async fn new(
+        config: Self::Config,
+        msg_submitter: Submitter<Message>,
+        event_submitter: Submitter<ApplicationEvent>,
+        keybindings: Keybindings<Message>,
+    ) -> Result<Self> {
         let (submitter, server) = channel();
         let (closer, closer_server) = channel();
+        let (redrawer, redrawer_server) = channel();
+        let (windower, windower_server) = channel();
+        let (clipboard, clipboard_server) = channel();
 
         let connection = Connection::connect_to_env()?;
 
-        let (globals, event_queue) = registry_queue_init::<State<Message>>(&connection)?;
+        let (globals, mut event_queue) = registry_queue_init::<State<Message>>(&connection)?;
         let qh = event_queue.handle();
 
         let compositor_state = CompositorState::bind(&globals, &qh)?;
         let xdg_shell = XdgShell::bind(&globals, &qh)?;
         let layer_shell = LayerShell::bind(&globals, &qh)?;
+        let shm = Shm::bind(&globals, &qh)?;
 
-        let instance = Instance::default();
+        // Only needed to decorate windows the compositor does not decorate.
+        let subcompositor =
+            SubcompositorState::bind(compositor_state.wl_compositor().clone(), &globals, &qh).ok();
 
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::LowPower,
+        // Both are optional, surfaces then fall back to the integer scale of their outputs.
+        let viewporter = globals.bind(&qh, 1..=1, ()).ok();
+        let fractional_scale_manager = globals.bind(&qh, 1..=1, ()).ok();
+
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: config.backends,
+            ..Default::default()
+        });
+
+        let adapter = Self::request_adapter(&instance, &config).await?;
+
+        tracing::info!("Using adapter {:?}", adapter.get_info());
+
+        let (device, queue) = adapter
+            .request_device(&DeviceDescriptor {
+                required_limits: config.limits.clone(),
                 ..Default::default()
             })
             .await?;
 
-        let (device, queue) = adapter.request_device(&Default::default()).await?;
+        let mut state = State::new(
+            msg_submitter,
+            event_submitter,
+            closer.clone(),
+            keybindings,
+            compositor_state.wl_compositor().clone(),
+            shm,
+            config.cursor_theme.clone(),
+            subcompositor,
+            config.decorations.clone(),
+            &globals,
+            &qh,
+        );
 
-        let state = State::new(msg_submitter, closer.clone(), &globals, &qh);
+        // Relative sizes are resolved against the outputs, so we need to know them before the
+        // first surface is created.
+        event_queue.roundtrip(&mut state)?;
 
         Ok(Self {
+            config,
+
             connection,
             event_queue,
             compositor_state,
             xdg_shell,
             layer_shell,
+            viewporter,
+            fractional_scale_manager,
 
             submitter,
             server,
             closer,
             closer_server,
+            redrawer,
+            redrawer_server,
+            windower,
+            windower_server,
+            clipboard,
+            clipboard_server,
 
             instance,
             adapter,
@@ -100,58 +179,123 @@ impl<Message: 'static + Send + Sync> Backend<Message> for WaylandBackend<Message
         self.closer.clone()
     }
 
+    fn redrawer(&self) -> Submitter<String> {
+        self.redrawer.clone()
+    }
+
+    fn windower(&self) -> Submitter<(String, WindowRequest)> {
+        self.windower.clone()
+    }
+
+    fn clipboard(&self) -> Submitter<ClipboardRequest> {
+        self.clipboard.clone()
+    }
+
     fn run(mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
         Box::pin(async move {
             tracing::info!("Wayland backend started");
 
+            // We only wake up when the compositor sent us something or when the application
+            // submitted a request, so idle surfaces cost nothing.
+            let fd = AsyncFd::with_interest(
+                self.connection.backend().poll_fd().as_raw_fd(),
+                Interest::READABLE,
+            )?;
+
             loop {
+                self.event_queue.dispatch_pending(&mut self.state)?;
+
+                for output in std::mem::take(&mut self.state.plugged) {
+                    let labels = self.state.templates.keys().cloned().collect::<Vec<_>>();
+
+                    for label in labels {
+                        self.replicate(&label, &output)?;
+                    }
+                }
+
+                self.event_queue.flush()?;
+
+                let Some(guard) = self.event_queue.prepare_read() else {
+                    continue;
+                };
+
+                // Held keys repeat and long presses are recognized without any event.
+                let timer = [self.state.next_repeat(), self.state.next_gesture()]
+                    .into_iter()
+                    .flatten()
+                    .min();
+
                 tokio::select! {
-                    _ = tokio::time::sleep(Duration::from_millis(16)) => {
-                        self.event_queue.flush()?;
+                    readable = fd.readable() => {
+                        let mut readable = readable?;
 
-                        if let Some(guard) = self.event_queue.prepare_read() {
-                            if let Err(e) = guard.read_without_dispatch() {
-                                eprintln!("Error reading events: {:?}", e);
-                            }
+                        match guard.read() {
+                            Ok(_) => {}
+                            Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {}
+                            Err(e) => return Err(e.into()),
                         }
 
-                        self.event_queue.dispatch_pending(&mut self.state).unwrap();
+                        readable.clear_ready();
+                    }
+                    _ = tokio::time::sleep_until(timer.unwrap_or_else(Instant::now)), if timer.is_some() => {
+                        self.state.repeat_keys();
+                        self.state.expire_gestures();
                     }
                     Ok(element) = self.server.recv() => {
-                        let mut lut = self.state.lut.clone();
-
                         for element in element.into_list() {
-                            if element.label().is_none() {
+                            let Some(label) = element.label() else {
                                 tracing::warn!("You submitted a widget with no label, which is forbidden.");
 
                                 continue;
-                            }
-
-                            let label = element.label().unwrap();
+                            };
 
-                            if !lut.contains_key(&label) {
-                                let widget =
-                                    WaylandWidget::new(self.create_wayland_surface(&element)?, element);
-
-                                lut.insert(label.clone(), widget.id.clone());
+                            if self.state.lut.contains_key(&label)
+                                || self.state.templates.contains_key(&label)
+                            {
+                                continue;
+                            }
 
-                                self.state
-                                    .lut
-                                    .insert(label.clone(), widget.id.clone());
+                            match element.downcast::<PerOutputWidget<Message>>() {
+                                Ok(template) => {
+                                    self.state.templates.insert(label.clone(), template);
 
-                                self.state
-                                    .views
-                                    .insert(widget.id.clone(), widget);
+                                    for output in self.state.outputs() {
+                                        self.replicate(&label, &output)?;
+                                    }
+                                }
+                                Err(element) => self.spawn(label, element.layout(), element, None)?,
                             }
                         }
-
                     },
                     Ok(label) = self.closer_server.recv() => {
-                        if let Some(id) = self.state.lut.remove(&label) {
-                            if let Some(widget) = self.state.views.remove(&id) {
-                                widget.destroy();
+                        if self.state.templates.remove(&label).is_some() {
+                            let replicas = self
+                                .state
+                                .views
+                                .values()
+                                .filter(|view| view.replica_of.as_ref() == Some(&label))
+                                .map(|view| view.label.clone())
+                                .collect::<Vec<_>>();
+
+                            for replica in replicas {
+                                self.state.destroy(&replica);
                             }
                         }
+
+                        self.state.destroy(&label);
+                    }
+                    Ok(label) = self.redrawer_server.recv() => {
+                        for widget in self.state.views.values_mut().filter(|view| {
+                            view.label == label || view.replica_of.as_ref() == Some(&label)
+                        }) {
+                            widget.request_redraw();
+                        }
+                    }
+                    Ok((label, request)) = self.windower_server.recv() => {
+                        self.state.request_window(&label, request);
+                    }
+                    Ok(request) = self.clipboard_server.recv() => {
+                        self.state.request_clipboard(request, &self.event_queue.handle());
                     }
                 }
             }
@@ -160,14 +304,141 @@ impl<Message: 'static + Send + Sync> Backend<Message> for WaylandBackend<Message
 }
 
 impl<Message: 'static + Send + Sync> WaylandBackend<Message> {
+    /// Creates the surface of an element placed with `layout`, and tracks it under `label`.
+    pub(crate) fn spawn(
+        &mut self,
+        label: String,
+        layout: Layout,
+        element: Element<Message>,
+        replica_of: Option<String>,
+    ) -> Result<()> {
+        if let Placement::Popup(popup) = &layout.placement {
+            if !self.state.lut.contains_key(&popup.parent) {
+                tracing::warn!(
+                    "The parent {} of the popup {} does not exist",
+                    popup.parent,
+                    label
+                );
+
+                return Ok(());
+            }
+        }
+
+        let mut widget = WaylandWidget::new(
+            label.clone(),
+            self.create_wayland_surface(label.clone(), &layout)?,
+            layout.size(self.state.output_size(layout.output.as_deref())),
+            element,
+            self.config.present_modes.clone(),
+            self.event_queue.handle(),
+        );
+
+        widget.output = layout.output;
+        widget.replica_of = replica_of;
+        widget.title = layout.toplevel.title.clone();
+        widget.parent = match &layout.placement {
+            Placement::Popup(popup) => Some(popup.parent.clone()),
+            _ => None,
+        };
+        widget.gestures = GestureRecognizer::new(self.config.gestures);
+
+        let qh = self.event_queue.handle();
+
+        widget.viewport = self
+            .viewporter
+            .as_ref()
+            .map(|viewporter| viewporter.get_viewport(widget.surface.wl_surface(), &qh, ()));
+
+        // A fractional scale can only be applied through a viewport.
+        if widget.viewport.is_some() {
+            widget.fractional_scale = self.fractional_scale_manager.as_ref().map(|manager| {
+                manager.get_fractional_scale(widget.surface.wl_surface(), &qh, widget.id.clone())
+            });
+        }
+
+        self.state.lut.insert(label, widget.id.clone());
+        self.state.views.insert(widget.id.clone(), widget);
+
+        Ok(())
+    }
+
+    /// Creates the instance of the per output widget `label` on an output, labelled
+    /// `label@output` after the key of the output.
+    pub(crate) fn replicate(&mut self, label: &str, output: &Output) -> Result<()> {
+        let replica = format!("{}@{}", label, output.key());
+
+        if self.state.lut.contains_key(&replica) {
+            return Ok(());
+        }
+
+        let Some(template) = self.state.templates.get(label) else {
+            return Ok(());
+        };
+
+        let element = template.build(output);
+
+        let mut layout = element.layout();
+        layout.output = Some(output.key());
+
+        self.spawn(replica, layout, element, Some(label.to_string()))
+    }
+
+    /// Requests the adapter named in the configuration, or falls back as configured.
+    pub(crate) async fn request_adapter(
+        instance: &Instance,
+        config: &WaylandConfig,
+    ) -> Result<Adapter> {
+        if let Some(name) = &config.adapter {
+            let name = name.to_lowercase();
+
+            let adapter = instance
+                .enumerate_adapters(config.backends)
+                .into_iter()
+                .find(|adapter| adapter.get_info().name.to_lowercase().contains(&name));
+
+            match (adapter, config.fallback) {
+                (Some(adapter), _) => return Ok(adapter),
+                (None, AdapterFallback::None) => {
+                    return Err(Report::msg(format!("No adapter named {}", name)));
+                }
+                (None, _) => {
+                    tracing::warn!("No adapter named {}, falling back", name);
+                }
+            }
+        }
+
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: config.power_preference,
+                ..Default::default()
+            })
+            .await;
+
+        match (adapter, config.fallback) {
+            (Err(e), AdapterFallback::Software) => {
+                tracing::warn!("No hardware adapter ({}), falling back to software", e);
+
+                Ok(instance
+                    .request_adapter(&RequestAdapterOptions {
+                        power_preference: config.power_preference,
+                        force_fallback_adapter: true,
+                        ..Default::default()
+                    })
+                    .await?)
+            }
+            (adapter, _) => Ok(adapter?),
+        }
+    }
+
     pub(crate) fn create_layer(
         &self,
-        layer: Layer,
+        layer: wlr_layer::Layer,
         label: String,
-        anchor: Anchor,
+        anchor: wlr_layer::Anchor,
         keyboard_interactivity: KeyboardInteractivity,
+        output: Option<&WlOutput>,
         size: (u32, u32),
-        exclusive_zone: u32,
+        exclusive_zone: i32,
         margin: (i32, i32, i32, i32),
     ) -> LayerSurface {
         let wl_surface = self
@@ -179,13 +450,13 @@ impl<Message: 'static + Send + Sync> WaylandBackend<Message> {
             wl_surface,
             layer,
             Some(label.clone()),
-            None,
+            output,
         );
 
         layer.set_anchor(anchor);
         layer.set_keyboard_interactivity(keyboard_interactivity);
         layer.set_size(size.0, size.1);
-        layer.set_exclusive_zone(exclusive_zone as i32);
+        layer.set_exclusive_zone(exclusive_zone);
         layer.set_margin(margin.0, margin.1, margin.2, margin.3);
 
         layer.commit();
@@ -197,8 +468,8 @@ impl<Message: 'static + Send + Sync> WaylandBackend<Message> {
         &self,
         decorations: WindowDecorations,
         label: String,
-        min_size: Option<(u32, u32)>,
-        max_size: Option<(u32, u32)>,
+        toplevel: &Toplevel,
+        output: Option<&WlOutput>,
     ) -> Window {
         let wl_surface = self
             .compositor_state
@@ -208,33 +479,122 @@ impl<Message: 'static + Send + Sync> WaylandBackend<Message> {
             self.xdg_shell
                 .create_window(wl_surface, decorations, &self.event_queue.handle());
 
-        window.set_title(&label);
-        window.set_app_id(&label);
-        window.set_min_size(min_size);
-        window.set_max_size(max_size);
+        window.set_title(toplevel.title.as_deref().unwrap_or(&label));
+        window.set_app_id(toplevel.app_id.as_deref().unwrap_or(&label));
+        window.set_min_size(toplevel.min_size);
+        window.set_max_size(toplevel.max_size);
+
+        if let Some(parent) = &toplevel.parent {
+            window.set_parent(self.state.window(parent));
+        }
+
+        if toplevel.maximized {
+            window.set_maximized();
+        }
+
+        if toplevel.fullscreen {
+            window.set_fullscreen(output);
+        }
 
         window.commit();
 
         window
     }
 
+    /// Creates a popup placed against its parent surface, which must exist. Popups of layer
+    /// surfaces have no xdg parent, the layer surface adopts them instead.
+    pub(crate) fn create_popup(&self, popup: &Popup, size: (u32, u32)) -> Result<SurfaceHandle> {
+        let qh = self.event_queue.handle();
+
+        let parent = self
+            .state
+            .lut
+            .get(&popup.parent)
+            .and_then(|id| self.state.views.get(id))
+            .ok_or_eyre("The parent of the popup does not exist")?;
+
+        // The anchor rectangle is in buffer pixels of the parent, the positioner expects logical
+        // pixels.
+        let scale = parent.scale;
+        let logical = |value: u32| (value as f64 / scale).round() as i32;
+
+        let rect = popup.anchor_rect;
+
+        let positioner = XdgPositioner::new(&self.xdg_shell)?;
+
+        positioner.set_size(size.0.max(1) as i32, size.1.max(1) as i32);
+        positioner.set_anchor_rect(
+            logical(rect.x),
+            logical(rect.y),
+            logical(rect.width).max(1),
+            logical(rect.height).max(1),
+        );
+        positioner.set_anchor(popup::anchor(popup.anchor));
+        positioner.set_gravity(popup::gravity(popup.gravity));
+        positioner.set_offset(popup.offset.0, popup.offset.1);
+        positioner.set_constraint_adjustment(popup::constraint_adjustment(popup.constraints));
+
+        // The popup then follows its parent when it moves or resizes.
+        if positioner.version() >= 3 {
+            positioner.set_reactive();
+        }
+
+        let xdg_parent = match &parent.surface {
+            SurfaceHandle::Window { window, .. } => Some(window.xdg_surface()),
+            SurfaceHandle::Popup { popup, .. } => Some(popup.xdg_surface()),
+            SurfaceHandle::Layer { .. } => None,
+        };
+
+        let xdg_popup = xdg::popup::Popup::from_surface(
+            xdg_parent,
+            &positioner,
+            &qh,
+            self.compositor_state.create_surface(&qh),
+            &self.xdg_shell,
+        )?;
+
+        if let Some(layer) = parent.surface.layer() {
+            layer.get_popup(xdg_popup.xdg_popup());
+        }
+
+        if popup.grab {
+            match self.state.last_press() {
+                Some((seat, serial)) => xdg_popup.xdg_popup().grab(seat, serial),
+                None => tracing::warn!("No press to grab the popup with"),
+            }
+        }
+
+        xdg_popup.wl_surface().commit();
+
+        Ok(SurfaceHandle::from_popup(
+            xdg_popup,
+            self.instance.clone(),
+            self.connection.clone(),
+            self.adapter.clone(),
+            self.device.clone(),
+            self.queue.clone(),
+        ))
+    }
+
     pub(crate) fn create_wayland_surface(
         &self,
-        element: &Element<Message>,
+        label: String,
+        layout: &Layout,
     ) -> Result<SurfaceHandle> {
-        let (anchor, exclusive) = match element.layout().placement {
-            Placement::Top => (Anchor::TOP, element.layout().height),
-            Placement::Bottom => (Anchor::BOTTOM, element.layout().height),
-            Placement::Left => (Anchor::LEFT, element.layout().width),
-            Placement::Right => (Anchor::RIGHT, element.layout().width),
+        let output = self.state.output(layout.output.as_deref());
+        let size = layout.size(self.state.output_size(layout.output.as_deref()));
+
+        let anchor = match layout.placement {
+            Placement::Top => Anchor::TOP,
+            Placement::Bottom => Anchor::BOTTOM,
+            Placement::Left => Anchor::LEFT,
+            Placement::Right => Anchor::RIGHT,
             Placement::Windowed => {
                 let window = self.create_window(
                     WindowDecorations::ServerDefault,
-                    element.label().ok_or_eyre(
-                        "Element must have a label in order to build a wayland layer",
-                    )?,
-                    None,
-                    None,
+                    label,
+                    &layout.toplevel,
+                    output.as_ref(),
                 );
 
                 return Ok(SurfaceHandle::from_window(
@@ -246,23 +606,45 @@ impl<Message: 'static + Send + Sync> WaylandBackend<Message> {
                     self.queue.clone(),
                 ));
             }
-            Placement::None => (Anchor::TOP | Anchor::LEFT, 0),
+            Placement::Popup(ref popup) => return self.create_popup(popup, size),
+            Placement::None => Anchor::TOP | Anchor::LEFT,
         };
 
+        let anchor = layout.anchor.unwrap_or(anchor);
+
         let layer = self.create_layer(
-            Layer::Top,
-            element
-                .label()
-                .ok_or_eyre("Element must have a label in order to build a wayland layer")?,
-            anchor,
-            match element.layout().keyboard_sensitivity {
+            match layout.layer.unwrap_or(self.config.layer) {
+                Layer::Background => wlr_layer::Layer::Background,
+                Layer::Bottom => wlr_layer::Layer::Bottom,
+                Layer::Top => wlr_layer::Layer::Top,
+                Layer::Overlay => wlr_layer::Layer::Overlay,
+            },
+            label,
+            [
+                (anchor.top, wlr_layer::Anchor::TOP),
+                (anchor.bottom, wlr_layer::Anchor::BOTTOM),
+                (anchor.left, wlr_layer::Anchor::LEFT),
+                (anchor.right, wlr_layer::Anchor::RIGHT),
+            ]
+            .into_iter()
+            .filter(|(anchored, _)| *anchored)
+            .fold(wlr_layer::Anchor::empty(), |anchor, (_, edge)| {
+                anchor | edge
+            }),
+            match layout.keyboard_sensitivity {
                 KeyboardSensitivity::None => KeyboardInteractivity::None,
                 KeyboardSensitivity::OnClick => KeyboardInteractivity::OnDemand,
                 KeyboardSensitivity::Exclusive => KeyboardInteractivity::Exclusive,
             },
-            (element.layout().width, element.layout().height),
-            exclusive,
-            (element.layout().y as i32, 0, 0, element.layout().x as i32),
+            output.as_ref(),
+            size,
+            layout.resolve_exclusive_zone(size),
+            (
+                layout.margin.top + layout.y as i32,
+                layout.margin.right,
+                layout.margin.bottom,
+                layout.margin.left + layout.x as i32,
+            ),
         );
 
         Ok(SurfaceHandle::from_layer(
@@ -1,48 +1,104 @@
 use std::collections::HashMap;
 
 use smithay_client_toolkit::{
+    data_device_manager::{DataDeviceManagerState, data_source::CopyPasteSource},
     delegate_registry,
     output::OutputState,
+    primary_selection::{PrimarySelectionManagerState, selection::PrimarySelectionSource},
+    reexports::client::protocol::wl_compositor::WlCompositor,
+    reexports::protocols::wp::{
+        pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+    },
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
     seat::SeatState,
+    shm::Shm,
+    subcompositor::SubcompositorState,
 };
+use tokio::time::Instant;
 use wayland_backend::client::ObjectId;
-use wayland_client::{
-    QueueHandle,
-    globals::GlobalList,
-    protocol::{wl_keyboard::WlKeyboard, wl_pointer::WlPointer},
-};
+use wayland_client::{QueueHandle, globals::GlobalList};
 
 use crate::prelude::*;
 
+pub(crate) mod clipboard;
 pub(crate) mod compositor;
+pub(crate) mod cursor;
+pub(crate) mod decorations;
 pub(crate) mod keyboard;
 pub(crate) mod layer;
 pub(crate) mod output;
 pub(crate) mod pointer;
+pub(crate) mod pointer_gestures;
+pub(crate) mod popup;
+pub(crate) mod scale;
 pub(crate) mod seat;
+pub(crate) mod text_input;
+pub(crate) mod touch;
 pub(crate) mod window;
 
 pub(crate) struct State<Message> {
     pub(crate) submitter: Submitter<Message>,
+    pub(crate) events: Submitter<ApplicationEvent>,
     pub(crate) closer: Submitter<String>,
+    /// The shortcuts of the application, matched before key presses reach the focused widget.
+    pub(crate) keybindings: Keybindings<Message>,
 
     pub(crate) views: HashMap<ObjectId, WaylandWidget<Message>>,
     pub(crate) lut: HashMap<String, ObjectId>,
 
+    /// The per output widgets, instantiated on every output.
+    pub(crate) templates: HashMap<String, Box<PerOutputWidget<Message>>>,
+    /// The outputs plugged in since the last dispatch, which still need their instances.
+    pub(crate) plugged: Vec<Output>,
+
     pub(crate) registry_state: RegistryState,
     pub(crate) seat_state: SeatState,
     pub(crate) output_state: OutputState,
 
-    pub(crate) keyboard: Option<WlKeyboard>,
-    pub(crate) pointer: Option<WlPointer>,
+    /// The seats advertised by the compositor, with their devices.
+    pub(crate) seats: HashMap<ObjectId, seat::Seat>,
+    /// The id of the next advertised seat. Protocol ids are reused once a seat is removed, these
+    /// never are, so a widget cannot confuse a new seat with a removed one.
+    pub(crate) next_seat: u32,
+    /// The seat that was pressed last, whose press grabbing popups are opened by.
+    pub(crate) last_pressed: Option<ObjectId>,
+
+    pub(crate) keyboards: HashMap<ObjectId, keyboard::KeyboardState>,
+    pub(crate) touches: HashMap<ObjectId, touch::TouchState>,
+
+    pub(crate) text_input_manager: Option<ZwpTextInputManagerV3>,
+    pub(crate) pointer_gestures: Option<ZwpPointerGesturesV1>,
+
+    pub(crate) data_device_manager: Option<DataDeviceManagerState>,
+    pub(crate) primary_selection_manager: Option<PrimarySelectionManagerState>,
+    /// The data the application put on the selections, offered until another client replaces it.
+    pub(crate) clipboard: Option<(CopyPasteSource, ClipboardData)>,
+    pub(crate) primary: Option<(PrimarySelectionSource, ClipboardData)>,
+
+    /// The compositor and shared memory, to create and fill the surfaces of the cursors.
+    pub(crate) compositor: WlCompositor,
+    pub(crate) shm: Shm,
+    pub(crate) cursor_theme: Option<CursorTheme>,
+
+    /// The decorations drawn around windows the compositor does not decorate, by window.
+    pub(crate) frames: HashMap<ObjectId, decorations::Frame>,
+    pub(crate) subcompositor: Option<SubcompositorState>,
+    pub(crate) decorations: Option<DecorationTheme>,
 }
 
 impl<Message: 'static + Send + Sync> State<Message> {
     pub(crate) fn new(
         submitter: Submitter<Message>,
+        events: Submitter<ApplicationEvent>,
         closer: Submitter<String>,
+        keybindings: Keybindings<Message>,
+        compositor: WlCompositor,
+        shm: Shm,
+        cursor_theme: Option<CursorTheme>,
+        subcompositor: Option<SubcompositorState>,
+        decorations: Option<DecorationTheme>,
         globals: &GlobalList,
         qh: &QueueHandle<Self>,
     ) -> Self {
@@ -51,35 +107,152 @@ impl<Message: 'static + Send + Sync> State<Message> {
             seat_state: SeatState::new(globals, qh),
             output_state: OutputState::new(globals, qh),
 
-            keyboard: None,
-            pointer: None,
+            seats: HashMap::new(),
+            next_seat: 0,
+            last_pressed: None,
+
+            keyboards: HashMap::new(),
+            touches: HashMap::new(),
+
+            // The input method is optional, text widgets then only receive key events.
+            text_input_manager: globals.bind(qh, 1..=1, ()).ok(),
+            // Touchpad gestures are optional too, widgets then only receive scroll events.
+            pointer_gestures: globals.bind(qh, 1..=3, ()).ok(),
+
+            // The selections are optional, reading them then fails and writing them is ignored.
+            data_device_manager: DataDeviceManagerState::bind(globals, qh).ok(),
+            primary_selection_manager: PrimarySelectionManagerState::bind(globals, qh).ok(),
+            clipboard: None,
+            primary: None,
+
+            compositor,
+            shm,
+            cursor_theme,
+
+            frames: HashMap::new(),
+            subcompositor,
+            decorations,
 
             submitter,
+            events,
             closer,
+            keybindings,
 
             views: HashMap::new(),
             lut: HashMap::new(),
+
+            templates: HashMap::new(),
+            plugged: Vec::new(),
+        }
+    }
+
+    /// Destroys the surface tracked under `label`, if any.
+    pub(crate) fn destroy(&mut self, label: &str) {
+        // The popups of the surface must be destroyed before it, from the topmost one.
+        let popups = self
+            .views
+            .values()
+            .filter(|view| view.parent.as_deref() == Some(label))
+            .map(|view| view.label.clone())
+            .collect::<Vec<_>>();
+
+        for popup in popups {
+            self.destroy(&popup);
+        }
+
+        if let Some(id) = self.lut.remove(label) {
+            for keyboard in self.keyboards.values_mut() {
+                if keyboard.focus.as_ref() == Some(&id) {
+                    keyboard.focus = None;
+                    keyboard.repeat.stop();
+                    keyboard.consumed.clear();
+                }
+            }
+
+            for touch in self.touches.values_mut() {
+                touch.forget(&id);
+            }
+
+            // The decorations are subsurfaces, destroyed before the window.
+            if let Some(frame) = self.frames.remove(&id) {
+                frame.destroy();
+            }
+
+            if let Some(widget) = self.views.remove(&id) {
+                widget.destroy();
+            }
+        }
+    }
+
+    /// The next time a long press may be recognized on a surface, if any.
+    pub(crate) fn next_gesture(&self) -> Option<Instant> {
+        self.views
+            .values()
+            .filter_map(|view| view.gestures.deadline())
+            .min()
+            .map(Instant::from_std)
+    }
+
+    /// Sends the long presses that are due, the backend calls it when `State::next_gesture` is
+    /// reached.
+    pub(crate) fn expire_gestures(&mut self) {
+        for view in self.views.values_mut() {
+            view.expire_gestures(self.submitter.clone());
         }
     }
 
     pub(crate) fn throw_event(&mut self, id: Option<ObjectId>, event: Event) {
+        let mut closed = Vec::new();
+
         if let Some(id) = id {
             if let Some(view) = self.views.get_mut(&id) {
-                if let Some(label) = view.on_event(event.clone(), self.submitter.clone()) {
-                    self.closer.submit(label).unwrap_or_else(|e| {
-                        tracing::error!("Failed to submit a close request for this label: {}", e);
-                    });
-                }
+                closed.extend(view.on_event(event.clone(), self.submitter.clone()));
             }
         } else {
             for view in self.views.values_mut() {
-                if let Some(label) = view.on_event(event.clone(), self.submitter.clone()) {
-                    self.closer.submit(label).unwrap_or_else(|e| {
-                        tracing::error!("Failed to submit a close request for this label: {}", e);
-                    });
-                }
+                closed.extend(view.on_event(event.clone(), self.submitter.clone()));
             }
         }
+
+        for label in closed {
+            self.closed(label);
+        }
+
+        self.update_text_input();
+    }
+
+    /// Handles a surface the compositor closed. The replica of a per output widget that still
+    /// exists is instantiated again on its output, other surfaces are closed by the backend.
+    fn closed(&mut self, label: String) {
+        let output = self
+            .lut
+            .get(&label)
+            .and_then(|id| self.views.get(id))
+            .filter(|view| {
+                view.replica_of
+                    .as_ref()
+                    .is_some_and(|template| self.templates.contains_key(template))
+            })
+            .and_then(|view| view.output.clone());
+
+        let Some(output) = output else {
+            self.closer.submit(label).unwrap_or_else(|e| {
+                tracing::error!("Failed to submit a close request for this label: {}", e);
+            });
+
+            return;
+        };
+
+        self.destroy(&label);
+
+        // The backend instantiates the missing replicas on plugged outputs after the dispatch.
+        if let Some(output) = self
+            .outputs()
+            .into_iter()
+            .find(|plugged| plugged.key() == output)
+        {
+            self.plugged.push(output);
+        }
     }
 }
 
@@ -0,0 +1,438 @@
+use std::os::fd::OwnedFd;
+
+use eyre::{OptionExt, eyre};
+use smithay_client_toolkit::{
+    data_device_manager::{
+        ReadPipe, WritePipe,
+        data_device::{DataDeviceData, DataDeviceHandler},
+        data_offer::{DataOfferHandler, DragOffer},
+        data_source::DataSourceHandler,
+    },
+    delegate_data_device, delegate_primary_selection,
+    primary_selection::{
+        device::{PrimarySelectionDeviceData, PrimarySelectionDeviceHandler},
+        selection::PrimarySelectionSourceHandler,
+    },
+    reexports::{
+        client::protocol::{
+            wl_data_device::WlDataDevice, wl_data_device_manager::DndAction,
+            wl_data_source::WlDataSource, wl_surface::WlSurface,
+        },
+        protocols::wp::primary_selection::zv1::client::{
+            zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+            zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+        },
+    },
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::unix::pipe,
+    sync::oneshot::Sender,
+};
+use wayland_client::{Connection, Proxy, QueueHandle};
+
+use crate::prelude::*;
+
+use super::seat::Seat;
+
+delegate_data_device!(@<Message: 'static + Send + Sync> State<Message>);
+delegate_primary_selection!(@<Message: 'static + Send + Sync> State<Message>);
+
+/// Reads a selection from the pipe its owner writes to, without blocking the backend.
+fn read(pipe: ReadPipe, reply: Sender<Result<Vec<u8>>>) {
+    tokio::spawn(async move {
+        let result = async {
+            let mut receiver = pipe::Receiver::from_owned_fd(OwnedFd::from(pipe))?;
+            let mut data = Vec::new();
+
+            receiver.read_to_end(&mut data).await?;
+
+            Ok::<_, Report>(data)
+        }
+        .await;
+
+        // The application may not wait for it anymore.
+        let _ = reply.send(result);
+    });
+}
+
+/// Writes a selection to the pipe of the client pasting it, without blocking the backend.
+fn write(pipe: WritePipe, data: Vec<u8>) {
+    tokio::spawn(async move {
+        let result = async {
+            let mut sender = pipe::Sender::from_owned_fd(OwnedFd::from(pipe))?;
+
+            sender.write_all(&data).await?;
+
+            Ok::<_, Report>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to send the selection: {}", e);
+        }
+    });
+}
+
+/// Reads data the application put on a selection itself, which is not read back through the
+/// compositor.
+fn read_owned(data: &ClipboardData, mime_type: &str) -> Result<Vec<u8>> {
+    data.get(mime_type)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| eyre!("The selection is not offered as {}", mime_type))
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// The seat the selections are used with, and the serial to set them with. The compositor
+    /// only lets the client focused by the keyboard use them, so it is the seat whose keyboard
+    /// focuses one of our surfaces, the one with the latest event if several do.
+    fn selection_seat(&self) -> Option<(&Seat, u32)> {
+        let keyboard = self
+            .keyboards
+            .values()
+            .filter(|keyboard| keyboard.focus.is_some())
+            .filter_map(|keyboard| Some((keyboard.seat, keyboard.serial?)))
+            .max_by_key(|(_, serial)| *serial)?;
+
+        self.seats
+            .values()
+            .find(|seat| seat.id == keyboard.0)
+            .map(|seat| (seat, keyboard.1))
+    }
+
+    /// The data the application put on a selection, while it still owns it.
+    fn owned_selection(&self, selection: Selection) -> Option<&ClipboardData> {
+        match selection {
+            Selection::Clipboard => self.clipboard.as_ref().map(|(_, data)| data),
+            Selection::Primary => self.primary.as_ref().map(|(_, data)| data),
+        }
+    }
+
+    /// Handles a request of the application about a selection.
+    pub(crate) fn request_clipboard(&mut self, request: ClipboardRequest, qh: &QueueHandle<Self>) {
+        match request {
+            ClipboardRequest::Read {
+                selection,
+                mime_type,
+                reply,
+            } => {
+                if let Some(data) = self.owned_selection(selection) {
+                    let _ = reply.send(read_owned(data, &mime_type));
+
+                    return;
+                }
+
+                match self.receive_selection(selection, mime_type) {
+                    Ok(pipe) => read(pipe, reply),
+                    Err(e) => {
+                        let _ = reply.send(Err(e));
+                    }
+                }
+            }
+            ClipboardRequest::Write { selection, data } => {
+                self.write_selection(selection, data, qh)
+            }
+        }
+    }
+
+    /// Asks the owner of a selection to send it in a MIME type.
+    fn receive_selection(&self, selection: Selection, mime_type: String) -> Result<ReadPipe> {
+        let (seat, _) = self
+            .selection_seat()
+            .ok_or_eyre("The selections can only be read while a window has the keyboard focus")?;
+
+        match selection {
+            Selection::Clipboard => {
+                let offer = seat
+                    .data_device
+                    .as_ref()
+                    .ok_or_eyre("The compositor does not support the clipboard")?
+                    .data()
+                    .selection_offer()
+                    .ok_or_eyre("The clipboard is empty")?;
+
+                if !offer.with_mime_types(|mime_types| mime_types.contains(&mime_type)) {
+                    return Err(eyre!("The clipboard is not offered as {}", mime_type));
+                }
+
+                Ok(offer.receive(mime_type)?)
+            }
+            Selection::Primary => {
+                let offer = seat
+                    .primary_selection
+                    .as_ref()
+                    .ok_or_eyre("The compositor does not support the primary selection")?
+                    .data()
+                    .selection_offer()
+                    .ok_or_eyre("The primary selection is empty")?;
+
+                if !offer.with_mime_types(|mime_types| mime_types.contains(&mime_type)) {
+                    return Err(eyre!(
+                        "The primary selection is not offered as {}",
+                        mime_type
+                    ));
+                }
+
+                Ok(offer.receive(mime_type)?)
+            }
+        }
+    }
+
+    /// Offers data on a selection, until another client replaces it.
+    fn write_selection(
+        &mut self,
+        selection: Selection,
+        data: ClipboardData,
+        qh: &QueueHandle<Self>,
+    ) {
+        // The compositor requires the serial of an input event of the focused client.
+        let Some((seat, serial)) = self.selection_seat() else {
+            tracing::warn!("The selections can only be set while a window has the keyboard focus");
+
+            return;
+        };
+
+        match selection {
+            Selection::Clipboard => {
+                let (Some(manager), Some(device)) = (&self.data_device_manager, &seat.data_device)
+                else {
+                    tracing::warn!("The compositor does not support the clipboard");
+
+                    return;
+                };
+
+                let source = manager.create_copy_paste_source(qh, data.mime_types());
+
+                source.set_selection(device, serial);
+
+                // The previous source is destroyed when dropped.
+                self.clipboard = Some((source, data));
+            }
+            Selection::Primary => {
+                let (Some(manager), Some(device)) =
+                    (&self.primary_selection_manager, &seat.primary_selection)
+                else {
+                    tracing::warn!("The compositor does not support the primary selection");
+
+                    return;
+                };
+
+                let source = manager.create_selection_source(qh, data.mime_types());
+
+                source.set_selection(device, serial);
+
+                self.primary = Some((source, data));
+            }
+        }
+    }
+
+    fn selection_changed(&self, selection: Selection, mime_types: Vec<String>) {
+        self.events
+            .submit(ApplicationEvent::SelectionChanged {
+                selection,
+                mime_types,
+            })
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to submit a selection change: {}", e);
+            });
+    }
+}
+
+impl<Message: 'static + Send + Sync> DataDeviceHandler for State<Message> {
+    // Drag and drop is not supported, only the selection is.
+    fn enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+        _x: f64,
+        _y: f64,
+        _wl_surface: &WlSurface,
+    ) {
+    }
+
+    fn leave(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _data_device: &WlDataDevice) {}
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+        _x: f64,
+        _y: f64,
+    ) {
+    }
+
+    fn selection(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        data_device: &WlDataDevice,
+    ) {
+        let mime_types = data_device
+            .data::<DataDeviceData>()
+            .and_then(|data| data.selection_offer())
+            .map(|offer| offer.with_mime_types(<[String]>::to_vec))
+            .unwrap_or_default();
+
+        self.selection_changed(Selection::Clipboard, mime_types);
+    }
+
+    fn drop_performed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+    ) {
+    }
+}
+
+impl<Message: 'static + Send + Sync> DataOfferHandler for State<Message> {
+    fn source_actions(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _offer: &mut DragOffer,
+        _actions: DndAction,
+    ) {
+    }
+
+    fn selected_action(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _offer: &mut DragOffer,
+        _actions: DndAction,
+    ) {
+    }
+}
+
+impl<Message: 'static + Send + Sync> DataSourceHandler for State<Message> {
+    fn accept_mime(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+        _mime: Option<String>,
+    ) {
+    }
+
+    fn send_request(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &WlDataSource,
+        mime: String,
+        fd: WritePipe,
+    ) {
+        let data = self
+            .clipboard
+            .as_ref()
+            .filter(|(owned, _)| owned.inner() == source)
+            .and_then(|(_, data)| data.get(&mime));
+
+        match data {
+            Some(data) => write(fd, data.to_vec()),
+            // Dropping the pipe closes it, so the client reads nothing.
+            None => tracing::warn!("The clipboard is not offered as {}", mime),
+        }
+    }
+
+    fn cancelled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &WlDataSource) {
+        if self
+            .clipboard
+            .as_ref()
+            .is_some_and(|(owned, _)| owned.inner() == source)
+        {
+            self.clipboard = None;
+        }
+    }
+
+    fn dnd_dropped(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _source: &WlDataSource) {
+    }
+
+    fn dnd_finished(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+    ) {
+    }
+
+    fn action(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+        _action: DndAction,
+    ) {
+    }
+}
+
+impl<Message: 'static + Send + Sync> PrimarySelectionDeviceHandler for State<Message> {
+    fn selection(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        primary_selection_device: &ZwpPrimarySelectionDeviceV1,
+    ) {
+        let mime_types = primary_selection_device
+            .data::<PrimarySelectionDeviceData>()
+            .and_then(|data| data.selection_offer())
+            .map(|offer| offer.with_mime_types(<[String]>::to_vec))
+            .unwrap_or_default();
+
+        self.selection_changed(Selection::Primary, mime_types);
+    }
+}
+
+impl<Message: 'static + Send + Sync> PrimarySelectionSourceHandler for State<Message> {
+    fn send_request(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+        mime: String,
+        write_pipe: WritePipe,
+    ) {
+        let data = self
+            .primary
+            .as_ref()
+            .filter(|(owned, _)| owned.inner() == source)
+            .and_then(|(_, data)| data.get(&mime));
+
+        match data {
+            Some(data) => write(write_pipe, data.to_vec()),
+            None => tracing::warn!("The primary selection is not offered as {}", mime),
+        }
+    }
+
+    fn cancelled(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+    ) {
+        if self
+            .primary
+            .as_ref()
+            .is_some_and(|(owned, _)| owned.inner() == source)
+        {
+            self.primary = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_owned_data_back() {
+        let data = ClipboardData::text("hej").with("text/html", "<b>hej</b>");
+
+        assert_eq!(read_owned(&data, "text/plain").unwrap(), b"hej");
+        assert_eq!(read_owned(&data, "text/html").unwrap(), b"<b>hej</b>");
+        assert!(read_owned(&data, "image/png").is_err());
+    }
+}
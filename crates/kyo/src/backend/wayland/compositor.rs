@@ -20,9 +20,26 @@ impl<Message: 'static + Send + Sync> CompositorHandler for State<Message> {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _new_factor: i32,
+        surface: &WlSurface,
+        new_factor: i32,
     ) {
+        // The fractional scale, when available, is more precise than the integer one.
+        if self
+            .views
+            .get(&surface.id())
+            .is_some_and(|view| view.fractional_scale.is_some())
+        {
+            return;
+        }
+
+        self.throw_event(
+            Some(surface.id()),
+            Event::ScaleChanged {
+                scale: new_factor as f64,
+            },
+        );
+
+        self.update_frame(&surface.id());
     }
 
     fn transform_changed(
@@ -0,0 +1,110 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::{
+    delegate_shm,
+    reexports::client::{Connection, protocol::wl_pointer::WlPointer},
+    seat::pointer::{CursorIcon, ThemeSpec},
+    shm::{Shm, ShmHandler},
+};
+
+delegate_shm!(@<Message: 'static + Send + Sync> State<Message>);
+
+impl<Message: 'static + Send + Sync> ShmHandler for State<Message> {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+/// Converts a cursor to the icon it stands for, `None` when it is hidden.
+fn icon(cursor: Cursor) -> Option<CursorIcon> {
+    Some(match cursor {
+        Cursor::Default => CursorIcon::Default,
+        Cursor::Pointer => CursorIcon::Pointer,
+        Cursor::Text => CursorIcon::Text,
+        Cursor::Crosshair => CursorIcon::Crosshair,
+        Cursor::Help => CursorIcon::Help,
+        Cursor::ContextMenu => CursorIcon::ContextMenu,
+        Cursor::Progress => CursorIcon::Progress,
+        Cursor::Wait => CursorIcon::Wait,
+        Cursor::NotAllowed => CursorIcon::NotAllowed,
+        Cursor::Move => CursorIcon::Move,
+        Cursor::Grab => CursorIcon::Grab,
+        Cursor::Grabbing => CursorIcon::Grabbing,
+        Cursor::NResize => CursorIcon::NResize,
+        Cursor::EResize => CursorIcon::EResize,
+        Cursor::SResize => CursorIcon::SResize,
+        Cursor::WResize => CursorIcon::WResize,
+        Cursor::NeResize => CursorIcon::NeResize,
+        Cursor::NwResize => CursorIcon::NwResize,
+        Cursor::SeResize => CursorIcon::SeResize,
+        Cursor::SwResize => CursorIcon::SwResize,
+        Cursor::EwResize => CursorIcon::EwResize,
+        Cursor::NsResize => CursorIcon::NsResize,
+        Cursor::NeswResize => CursorIcon::NeswResize,
+        Cursor::NwseResize => CursorIcon::NwseResize,
+        Cursor::ColResize => CursorIcon::ColResize,
+        Cursor::RowResize => CursorIcon::RowResize,
+        Cursor::ZoomIn => CursorIcon::ZoomIn,
+        Cursor::ZoomOut => CursorIcon::ZoomOut,
+        Cursor::Hidden => return None,
+    })
+}
+
+/// The theme of the cursors drawn by the client, when the compositor does not support cursor
+/// shapes.
+pub(crate) fn theme_spec(theme: Option<&CursorTheme>) -> ThemeSpec<'_> {
+    match theme {
+        Some(theme) => ThemeSpec::Named {
+            name: &theme.name,
+            size: theme.size,
+        },
+        None => ThemeSpec::System,
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Shows the cursor asked by the widget under the pointer, when it changed. The compositor
+    /// draws it from its shape when it supports `wp_cursor_shape_v1`, otherwise it is loaded from
+    /// the cursor theme.
+    pub(crate) fn update_cursor(&mut self, conn: &Connection, pointer: &WlPointer) {
+        let Some(focus) = self
+            .pointer_seat(pointer)
+            .and_then(|seat| seat.pointer_focus.clone())
+        else {
+            return;
+        };
+
+        let Some(cursor) = self.views.get(&focus).map(|view| view.widget.cursor()) else {
+            return;
+        };
+
+        self.set_cursor(conn, pointer, cursor);
+    }
+
+    /// Shows a cursor for the pointer, unless it is already shown.
+    pub(crate) fn set_cursor(&mut self, conn: &Connection, pointer: &WlPointer, cursor: Cursor) {
+        let Some(seat) = self.pointer_seat(pointer) else {
+            return;
+        };
+
+        let Some(themed) = &seat.pointer else {
+            return;
+        };
+
+        if seat.cursor == Some(cursor) {
+            return;
+        }
+
+        let result = match icon(cursor) {
+            Some(icon) => themed.set_cursor(conn, icon),
+            None => themed.hide_cursor(),
+        };
+
+        // A cursor missing from the theme is not retried on every motion.
+        seat.cursor = Some(cursor);
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to set the cursor {:?}: {}", cursor, e);
+        }
+    }
+}
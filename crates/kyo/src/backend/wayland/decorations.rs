@@ -0,0 +1,699 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::{
+    delegate_subcompositor,
+    reexports::{
+        client::{
+            Connection, QueueHandle,
+            protocol::{
+                wl_pointer::WlPointer, wl_shm, wl_subsurface::WlSubsurface, wl_surface::WlSurface,
+            },
+        },
+        protocols::xdg::shell::client::xdg_toplevel::ResizeEdge,
+    },
+    seat::pointer::{PointerEvent, PointerEventKind},
+    shm::{
+        Shm,
+        slot::{Buffer, SlotPool},
+    },
+    subcompositor::SubcompositorState,
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::Proxy;
+
+delegate_subcompositor!(@<Message: 'static + Send + Sync> State<Message>);
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
+/// The buttons of the titlebar, from right to left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Button {
+    Close,
+    Maximize,
+    Minimize,
+}
+
+const BUTTONS: [Button; 3] = [Button::Close, Button::Maximize, Button::Minimize];
+
+/// A part of the decorations, which reacts to the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Titlebar,
+    Button(Button),
+    Edge(Edges),
+}
+
+/// Converts the edges grabbed by the pointer to the edge of an interactive resize.
+pub(crate) fn resize_edge(edges: Edges) -> ResizeEdge {
+    match (edges.top, edges.bottom, edges.left, edges.right) {
+        (true, _, true, _) => ResizeEdge::TopLeft,
+        (true, _, _, true) => ResizeEdge::TopRight,
+        (_, true, true, _) => ResizeEdge::BottomLeft,
+        (_, true, _, true) => ResizeEdge::BottomRight,
+        (true, _, _, _) => ResizeEdge::Top,
+        (_, true, _, _) => ResizeEdge::Bottom,
+        (_, _, true, _) => ResizeEdge::Left,
+        (_, _, _, true) => ResizeEdge::Right,
+        _ => ResizeEdge::None,
+    }
+}
+
+/// The cursor shown over the edges of a window.
+pub(crate) fn resize_cursor(edges: Edges) -> Cursor {
+    match resize_edge(edges) {
+        ResizeEdge::TopLeft => Cursor::NwResize,
+        ResizeEdge::TopRight => Cursor::NeResize,
+        ResizeEdge::BottomLeft => Cursor::SwResize,
+        ResizeEdge::BottomRight => Cursor::SeResize,
+        ResizeEdge::Top => Cursor::NResize,
+        ResizeEdge::Bottom => Cursor::SResize,
+        ResizeEdge::Left => Cursor::WResize,
+        ResizeEdge::Right => Cursor::EResize,
+        _ => Cursor::Default,
+    }
+}
+
+/// The part of the decorations at a position relative to a window of `size`, in logical pixels.
+/// The titlebar is above the window and the resize border around both of them.
+fn part_at(titlebar: u32, size: (u32, u32), position: (f64, f64)) -> Part {
+    let titlebar = titlebar as f64;
+    let (width, height) = (size.0 as f64, size.1 as f64);
+
+    let (x, y) = position;
+
+    let edges = Edges {
+        top: y < -titlebar,
+        bottom: y >= height,
+        left: x < 0.0,
+        right: x >= width,
+    };
+
+    if edges != Edges::default() {
+        return Part::Edge(edges);
+    }
+
+    let from_right = ((width - x) / titlebar).floor();
+
+    match BUTTONS.get(from_right as usize) {
+        Some(button) if y < 0.0 && from_right >= 0.0 => Part::Button(*button),
+        _ => Part::Titlebar,
+    }
+}
+
+/// A subsurface of the decorations, placed below the window.
+struct Piece {
+    surface: WlSurface,
+    subsurface: WlSubsurface,
+
+    /// The buffer attached to the surface, kept until the compositor releases it.
+    buffer: Option<Buffer>,
+    /// The position of the surface relative to the window, in logical pixels.
+    position: (i32, i32),
+}
+
+impl Piece {
+    fn new<Message: 'static + Send + Sync>(
+        subcompositor: &SubcompositorState,
+        parent: &WlSurface,
+        qh: &QueueHandle<State<Message>>,
+    ) -> Self {
+        let (subsurface, surface) = subcompositor.create_subsurface(parent.clone(), qh);
+
+        // The decorations are redrawn on hover without waiting for the window to commit.
+        subsurface.set_desync();
+        subsurface.place_below(parent);
+
+        Self {
+            surface,
+            subsurface,
+
+            buffer: None,
+            position: (0, 0),
+        }
+    }
+
+    /// Moves the surface, which takes effect with the next commit of the window.
+    fn place(&mut self, position: (i32, i32)) {
+        if self.position != position {
+            self.subsurface.set_position(position.0, position.1);
+            self.position = position;
+        }
+    }
+
+    /// Attaches the buffer of the piece and commits all of it.
+    fn commit(&self) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        if let Err(e) = buffer.attach_to(&self.surface) {
+            tracing::warn!("Failed to attach the buffer of the decorations: {}", e);
+
+            return;
+        }
+
+        self.surface
+            .damage_buffer(0, 0, buffer.stride() / 4, buffer.height());
+        self.surface.commit();
+    }
+
+    /// Covers a rectangle relative to the window, in logical pixels, with a transparent buffer
+    /// that only catches the pointer, or unmaps the surface when the rectangle is empty. The
+    /// buffer is only replaced when the size of the rectangle changes.
+    fn cover(&mut self, pool: &mut SlotPool, rect: (i32, i32, i32, i32)) {
+        let (x, y, width, height) = rect;
+
+        self.place((x, y));
+
+        if width <= 0 || height <= 0 {
+            if self.buffer.take().is_some() {
+                self.surface.attach(None, 0, 0);
+                self.surface.commit();
+            }
+
+            return;
+        }
+
+        let unchanged = self
+            .buffer
+            .as_ref()
+            .is_some_and(|buffer| buffer.stride() == width * 4 && buffer.height() == height);
+
+        if unchanged {
+            return;
+        }
+
+        match pool.create_buffer(width, height, width * 4, wl_shm::Format::Argb8888) {
+            Ok((buffer, pixels)) => {
+                pixels.fill(0);
+
+                self.buffer = Some(buffer);
+                self.commit();
+            }
+            Err(e) => tracing::warn!("Failed to create a buffer for the decorations: {}", e),
+        }
+    }
+
+    fn destroy(&self) {
+        self.subsurface.destroy();
+        self.surface.destroy();
+    }
+}
+
+/// The decorations of a window: a titlebar above it and an invisible border around both, made of
+/// subsurfaces placed below the window.
+pub(crate) struct Frame {
+    titlebar: Piece,
+    /// The top, bottom, left and right sides of the resize border.
+    sides: [Piece; 4],
+
+    pool: SlotPool,
+
+    /// The state of the window, from its last configure.
+    pub(crate) state: WindowState,
+
+    hovered: Option<Part>,
+    pressed: Option<Part>,
+}
+
+impl Frame {
+    pub(crate) fn new<Message: 'static + Send + Sync>(
+        subcompositor: &SubcompositorState,
+        shm: &Shm,
+        parent: &WlSurface,
+        qh: &QueueHandle<State<Message>>,
+    ) -> Option<Self> {
+        let pool = match SlotPool::new(256 * 32 * 4, shm) {
+            Ok(pool) => pool,
+            Err(e) => {
+                tracing::warn!("Failed to create the pool of the decorations: {}", e);
+
+                return None;
+            }
+        };
+
+        Some(Self {
+            titlebar: Piece::new(subcompositor, parent, qh),
+            sides: std::array::from_fn(|_| Piece::new(subcompositor, parent, qh)),
+
+            pool,
+
+            state: WindowState::default(),
+
+            hovered: None,
+            pressed: None,
+        })
+    }
+
+    /// The width of the resize border, which maximized and tiled windows do not have.
+    fn border(&self, theme: &DecorationTheme) -> u32 {
+        let tiled = self.state.tiled;
+
+        if self.state.maximized || tiled.top || tiled.bottom || tiled.left || tiled.right {
+            0
+        } else {
+            theme.resize_border
+        }
+    }
+
+    fn pieces(&self) -> impl Iterator<Item = &Piece> {
+        std::iter::once(&self.titlebar).chain(&self.sides)
+    }
+
+    /// Converts a position on one of the surfaces of the frame to a position relative to the
+    /// window, in logical pixels.
+    fn window_position(&self, surface: &ObjectId, position: (f64, f64)) -> Option<(f64, f64)> {
+        self.pieces()
+            .find(|piece| piece.surface.id() == *surface)
+            .map(|piece| {
+                (
+                    position.0 + piece.position.0 as f64,
+                    position.1 + piece.position.1 as f64,
+                )
+            })
+    }
+
+    /// Draws the decorations of a window of `size`, in logical pixels, at an integer scale.
+    fn draw(&mut self, theme: &DecorationTheme, title: &str, size: (u32, u32), scale: f64) {
+        let scale = scale.ceil().max(1.0) as u32;
+        let border = self.border(theme) as i32;
+        let titlebar = theme.titlebar_height;
+
+        let (width, height, bar) = (size.0 as i32, size.1 as i32, titlebar as i32);
+
+        let [top, bottom, left, right] = &mut self.sides;
+
+        top.cover(
+            &mut self.pool,
+            (-border, -bar - border, width + border * 2, border),
+        );
+        bottom.cover(
+            &mut self.pool,
+            (-border, height, width + border * 2, border),
+        );
+        left.cover(&mut self.pool, (-border, -bar, border, height + bar));
+        right.cover(&mut self.pool, (width, -bar, border, height + bar));
+
+        self.titlebar.place((0, -bar));
+
+        let (bar_width, bar_height) = (size.0 * scale, titlebar * scale);
+        let stride = bar_width as usize * 4;
+
+        if bar_width == 0 || bar_height == 0 {
+            return;
+        }
+
+        // The buffer is redrawn in place once the compositor released it, and only replaced
+        // when the size changes or the compositor still reads it.
+        let reusable = self.titlebar.buffer.as_ref().is_some_and(|buffer| {
+            buffer.stride() == stride as i32
+                && buffer.height() == bar_height as i32
+                && buffer.canvas(&mut self.pool).is_some()
+        });
+
+        if !reusable {
+            match self.pool.create_buffer(
+                bar_width as i32,
+                bar_height as i32,
+                stride as i32,
+                wl_shm::Format::Argb8888,
+            ) {
+                Ok((buffer, _)) => self.titlebar.buffer = Some(buffer),
+                Err(e) => {
+                    tracing::warn!("Failed to create a buffer for the decorations: {}", e);
+
+                    return;
+                }
+            }
+        }
+
+        let Some(pixels) = self
+            .titlebar
+            .buffer
+            .as_ref()
+            .and_then(|buffer| buffer.canvas(&mut self.pool))
+        else {
+            return;
+        };
+
+        // The colors may be translucent, so nothing of the previous frame may show through.
+        pixels.fill(0);
+
+        let colors = if self.state.activated {
+            theme.active
+        } else {
+            theme.inactive
+        };
+
+        let mut canvas = TitlebarCanvas::new(
+            &mut *pixels,
+            stride,
+            (0, 0),
+            (bar_width, bar_height),
+            scale,
+            title,
+            colors.foreground,
+        );
+
+        canvas.fill(Rect::new(0, 0, bar_width, bar_height), colors.background);
+
+        for (i, button) in BUTTONS.into_iter().enumerate() {
+            let rect = Rect::new(
+                bar_width.saturating_sub((i as u32 + 1) * bar_height),
+                0,
+                bar_height,
+                bar_height,
+            );
+
+            if self.hovered == Some(Part::Button(button)) {
+                let color = match button {
+                    Button::Close => colors.close_hovered,
+                    _ => colors.hovered,
+                };
+
+                canvas.fill(rect, color);
+            }
+
+            draw_icon(&mut canvas, button, rect, colors.foreground);
+        }
+
+        let title_width = bar_width.saturating_sub(BUTTONS.len() as u32 * bar_height);
+
+        let mut canvas = TitlebarCanvas::new(
+            pixels,
+            stride,
+            (0, 0),
+            (title_width, bar_height),
+            scale,
+            title,
+            colors.foreground,
+        );
+
+        match &theme.title_painter {
+            Some(painter) => painter(&mut canvas),
+            None => canvas.draw_title(),
+        }
+
+        self.titlebar.surface.set_buffer_scale(scale as i32);
+        self.titlebar.commit();
+    }
+
+    pub(crate) fn destroy(&self) {
+        for piece in self.pieces() {
+            piece.destroy();
+        }
+    }
+}
+
+/// The button a part is, if any.
+fn hovered_button(part: Option<Part>) -> Option<Button> {
+    match part {
+        Some(Part::Button(button)) => Some(button),
+        _ => None,
+    }
+}
+
+/// Draws the icon of a button, centered in its rectangle.
+fn draw_icon(canvas: &mut TitlebarCanvas<'_>, button: Button, rect: Rect, color: [u8; 4]) {
+    let size = rect.height / 3;
+    let thickness = canvas.scale();
+
+    let x = rect.x + (rect.width - size) / 2;
+    let y = rect.y + (rect.height - size) / 2;
+
+    match button {
+        Button::Close => {
+            for i in 0..size {
+                for t in 0..thickness {
+                    canvas.blend(x + i, y + (i + t).min(size - 1), color);
+                    canvas.blend(x + i, y + (size - 1 - i).saturating_sub(t), color);
+                }
+            }
+        }
+        Button::Maximize => {
+            canvas.fill(Rect::new(x, y, size, thickness), color);
+            canvas.fill(Rect::new(x, y + size - thickness, size, thickness), color);
+            canvas.fill(Rect::new(x, y, thickness, size), color);
+            canvas.fill(Rect::new(x + size - thickness, y, thickness, size), color);
+        }
+        Button::Minimize => {
+            canvas.fill(Rect::new(x, y + size - thickness, size, thickness), color);
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Adds or removes the decorations of a window, as negotiated with the compositor.
+    pub(crate) fn decorate(
+        &mut self,
+        id: &ObjectId,
+        decorated: bool,
+        state: WindowState,
+        qh: &QueueHandle<Self>,
+    ) {
+        if !decorated {
+            if let Some(frame) = self.frames.remove(id) {
+                frame.destroy();
+            }
+
+            return;
+        }
+
+        if !self.frames.contains_key(id) {
+            let (Some(subcompositor), Some(view)) = (&self.subcompositor, self.views.get(id))
+            else {
+                return;
+            };
+
+            let Some(frame) = Frame::new(subcompositor, &self.shm, view.surface.wl_surface(), qh)
+            else {
+                return;
+            };
+
+            self.frames.insert(id.clone(), frame);
+        }
+
+        if let Some(frame) = self.frames.get_mut(id) {
+            frame.state = state;
+        }
+    }
+
+    /// Draws the decorations of a window for its current size and scale, and sets its geometry
+    /// so that the compositor counts the titlebar, but not the resize border, as the window.
+    pub(crate) fn update_frame(&mut self, id: &ObjectId) {
+        let Some(theme) = &self.decorations else {
+            return;
+        };
+
+        let Some(view) = self.views.get(id) else {
+            return;
+        };
+
+        let Some(window) = view.surface.window() else {
+            return;
+        };
+
+        let (width, height) = (view.size.0 as i32, view.size.1 as i32);
+
+        match self.frames.get_mut(id) {
+            Some(frame) => {
+                let title = view.title.as_deref().unwrap_or(&view.label);
+
+                frame.draw(theme, title, view.size, view.scale);
+
+                let titlebar = theme.titlebar_height as i32;
+
+                window
+                    .xdg_surface()
+                    .set_window_geometry(0, -titlebar, width, height + titlebar);
+            }
+            None => window
+                .xdg_surface()
+                .set_window_geometry(0, 0, width, height),
+        }
+
+        window.commit();
+    }
+
+    /// The window whose decorations are drawn on a surface, if any.
+    pub(crate) fn frame_owner(&self, surface: &ObjectId) -> Option<ObjectId> {
+        self.frames
+            .iter()
+            .find(|(_, frame)| frame.pieces().any(|piece| piece.surface.id() == *surface))
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Handles a pointer event on the decorations of a window: the titlebar moves the window
+    /// and shows its menu, the border resizes it and the buttons act on release.
+    pub(crate) fn frame_pointer_event(
+        &mut self,
+        conn: &Connection,
+        pointer: &WlPointer,
+        id: ObjectId,
+        event: &PointerEvent,
+    ) {
+        let Some(seat) = self.pointer_seat(pointer).map(|seat| seat.seat.clone()) else {
+            return;
+        };
+
+        let (Some(theme), Some(view)) = (&self.decorations, self.views.get(&id)) else {
+            return;
+        };
+
+        let Some(window) = view.surface.window() else {
+            return;
+        };
+
+        let Some(frame) = self.frames.get_mut(&id) else {
+            return;
+        };
+
+        let Some(position) = frame.window_position(&event.surface.id(), event.position) else {
+            return;
+        };
+
+        let part = part_at(theme.titlebar_height, view.size, position);
+
+        let mut redraw = false;
+        let mut cursor = None;
+        let mut close = false;
+
+        match event.kind {
+            PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                // Only the buttons show that they are hovered.
+                redraw = hovered_button(frame.hovered) != hovered_button(Some(part));
+                frame.hovered = Some(part);
+
+                cursor = Some(match part {
+                    Part::Edge(edges) => resize_cursor(edges),
+                    _ => Cursor::Default,
+                });
+            }
+            PointerEventKind::Leave { .. } => {
+                redraw = hovered_button(frame.hovered).is_some();
+                frame.hovered = None;
+                frame.pressed = None;
+            }
+            PointerEventKind::Press {
+                button: BTN_LEFT,
+                serial,
+                ..
+            } => match part {
+                Part::Titlebar => window.move_(&seat, serial),
+                Part::Edge(edges) => window.resize(&seat, serial, resize_edge(edges)),
+                Part::Button(_) => frame.pressed = Some(part),
+            },
+            PointerEventKind::Press {
+                button: BTN_RIGHT,
+                serial,
+                ..
+            } if part == Part::Titlebar => {
+                // The position is relative to the window surface, so above it on the titlebar.
+                window.show_window_menu(&seat, serial, (position.0 as i32, position.1 as i32));
+            }
+            PointerEventKind::Release {
+                button: BTN_LEFT, ..
+            } => {
+                if frame.pressed.take() == Some(part) {
+                    match part {
+                        Part::Button(Button::Close) => close = true,
+                        Part::Button(Button::Maximize) if frame.state.maximized => {
+                            window.unset_maximized()
+                        }
+                        Part::Button(Button::Maximize) => window.set_maximized(),
+                        Part::Button(Button::Minimize) => window.set_minimized(),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(cursor) = cursor {
+            self.set_cursor(conn, pointer, cursor);
+        }
+
+        if redraw {
+            self.update_frame(&id);
+        }
+
+        if close {
+            self.throw_event(Some(id), Event::Close);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The edges named by the initials of their sides, e.g. `"tl"` for the top left corner.
+    fn edges(sides: &str) -> Edges {
+        Edges {
+            top: sides.contains('t'),
+            bottom: sides.contains('b'),
+            left: sides.contains('l'),
+            right: sides.contains('r'),
+        }
+    }
+
+    /// The part at a position around a window of 200x100 with a titlebar of 32.
+    fn part(x: f64, y: f64) -> Part {
+        part_at(32, (200, 100), (x, y))
+    }
+
+    #[test]
+    fn converts_edges_to_resize_edges() {
+        let cases = [
+            ("t", ResizeEdge::Top),
+            ("b", ResizeEdge::Bottom),
+            ("l", ResizeEdge::Left),
+            ("r", ResizeEdge::Right),
+            ("tl", ResizeEdge::TopLeft),
+            ("tr", ResizeEdge::TopRight),
+            ("bl", ResizeEdge::BottomLeft),
+            ("br", ResizeEdge::BottomRight),
+            ("", ResizeEdge::None),
+        ];
+
+        for (sides, edge) in cases {
+            assert_eq!(resize_edge(edges(sides)), edge, "{sides}");
+        }
+    }
+
+    #[test]
+    fn finds_the_corners() {
+        assert_eq!(part(-4.0, -36.0), Part::Edge(edges("tl")));
+        assert_eq!(part(203.0, -40.0), Part::Edge(edges("tr")));
+        assert_eq!(part(-1.0, 100.0), Part::Edge(edges("bl")));
+        assert_eq!(part(200.0, 107.0), Part::Edge(edges("br")));
+    }
+
+    #[test]
+    fn finds_the_edges() {
+        assert_eq!(part(100.0, -33.0), Part::Edge(edges("t")));
+        assert_eq!(part(100.0, 100.0), Part::Edge(edges("b")));
+        assert_eq!(part(-0.5, 50.0), Part::Edge(edges("l")));
+        assert_eq!(part(200.0, 50.0), Part::Edge(edges("r")));
+
+        // The sides of the border run along the titlebar too.
+        assert_eq!(part(-2.0, -16.0), Part::Edge(edges("l")));
+        assert_eq!(part(201.0, -16.0), Part::Edge(edges("r")));
+    }
+
+    #[test]
+    fn finds_the_buttons_from_the_right() {
+        assert_eq!(part(199.0, -16.0), Part::Button(Button::Close));
+        assert_eq!(part(169.0, -1.0), Part::Button(Button::Close));
+        assert_eq!(part(150.0, -32.0), Part::Button(Button::Maximize));
+        assert_eq!(part(120.0, -16.0), Part::Button(Button::Minimize));
+    }
+
+    #[test]
+    fn finds_the_titlebar() {
+        assert_eq!(part(0.0, -32.0), Part::Titlebar);
+        assert_eq!(part(100.0, -16.0), Part::Titlebar);
+        assert_eq!(part(103.0, -1.0), Part::Titlebar);
+    }
+}
@@ -1,3 +1,5 @@
+use std::{collections::HashSet, time::Duration};
+
 use crate::prelude::*;
 
 use smithay_client_toolkit::{
@@ -6,89 +8,470 @@ use smithay_client_toolkit::{
         Connection, QueueHandle,
         protocol::{wl_keyboard::WlKeyboard, wl_surface::WlSurface},
     },
-    seat::keyboard::{KeyEvent, KeyboardHandler, Keysym, Modifiers, RawModifiers},
+    seat::keyboard::{
+        KeyEvent as XkbKeyEvent, KeyboardHandler, Keysym, Modifiers as XkbModifiers, RawModifiers,
+        RepeatInfo,
+    },
 };
+use tokio::time::Instant;
+use wayland_backend::client::ObjectId;
 use wayland_client::Proxy;
 
 delegate_keyboard!(@<Message: 'static + Send + Sync> State<Message>);
 
+/// The state of a keyboard, tracked across its events.
+pub(crate) struct KeyboardState {
+    pub(crate) seat: SeatId,
+
+    /// The surface focused by the keyboard, which receives its key events.
+    pub(crate) focus: Option<ObjectId>,
+    /// The serial of the last enter or key press of the keyboard, with which the application
+    /// sets the selections while it has the focus.
+    pub(crate) serial: Option<u32>,
+    pub(crate) modifiers: Modifiers,
+
+    pub(crate) repeat: KeyRepeat,
+    /// The codes of the held keys whose press was consumed by a shortcut, so that their
+    /// release is not delivered either.
+    pub(crate) consumed: HashSet<u32>,
+}
+
+impl KeyboardState {
+    pub(crate) fn new(seat: SeatId) -> Self {
+        Self {
+            seat,
+            focus: None,
+            serial: None,
+            modifiers: Modifiers::default(),
+            repeat: KeyRepeat::default(),
+            consumed: HashSet::new(),
+        }
+    }
+}
+
+/// Repeats the held key of a keyboard. The compositor only sends the repeat rate and delay, the
+/// backend then wakes up on `KeyRepeat::deadline` to synthesize the presses.
+pub(crate) struct KeyRepeat {
+    /// The delay before a held key repeats and the interval between repeats, `None` when keys
+    /// are not repeated.
+    info: Option<(Duration, Duration)>,
+    /// The held key, and when it repeats next.
+    held: Option<(KeyEvent, Instant)>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            // Until the compositor tells us otherwise, 25 repeats per second after 600ms.
+            info: Some((Duration::from_millis(600), Duration::from_millis(40))),
+            held: None,
+        }
+    }
+}
+
+impl KeyRepeat {
+    /// Starts repeating a pressed key after the delay. Modifiers are never repeated, and do not
+    /// stop the repeat of the held key.
+    fn press(&mut self, event: &KeyEvent, now: Instant) {
+        if matches!(event.key, Key::Named(named) if named.is_modifier()) {
+            return;
+        }
+
+        self.held = self.info.map(|(delay, _)| (event.clone(), now + delay));
+    }
+
+    /// Stops repeating the key with this code, if it is the held one.
+    fn release(&mut self, code: u32) {
+        if self
+            .held
+            .as_ref()
+            .is_some_and(|(held, _)| held.code == code)
+        {
+            self.held = None;
+        }
+    }
+
+    /// Stops repeating the held key, e.g. when the focus is lost.
+    pub(crate) fn stop(&mut self) {
+        self.held = None;
+    }
+
+    /// Applies the repeat rate and delay of the compositor, `None` disabling the repeat.
+    fn configure(&mut self, info: Option<(Duration, Duration)>) {
+        self.info = info;
+
+        if info.is_none() {
+            self.held = None;
+        }
+    }
+
+    /// The next time the held key repeats, if any.
+    fn deadline(&self) -> Option<Instant> {
+        self.held.as_ref().map(|(_, at)| *at)
+    }
+
+    /// Returns a new press of the held key when it is due, and schedules the next one.
+    fn repeat(&mut self, now: Instant, modifiers: Modifiers) -> Option<KeyEvent> {
+        let ((event, at), (_, interval)) = (self.held.as_mut()?, self.info?);
+
+        if *at > now {
+            return None;
+        }
+
+        *at = now + interval;
+
+        Some(KeyEvent {
+            modifiers,
+            repeat: true,
+            ..event.clone()
+        })
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Sends an event to the surface focused by the keyboard, key events are dropped when no
+    /// surface has the focus. Key presses are first matched against the shortcuts of the focused
+    /// widget, then against the keybindings of the application. The presses they consume and
+    /// the releases of these keys are not delivered. Returns whether the event was delivered.
+    fn throw_keyboard_event(&mut self, keyboard: &WlKeyboard, event: Event) -> bool {
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return false;
+        };
+
+        let Some(surface) = state.focus.clone() else {
+            return false;
+        };
+
+        match &event {
+            Event::KeyPressed(key) => {
+                let Some(view) = self.views.get_mut(&surface) else {
+                    return false;
+                };
+
+                let consumed = view.on_shortcut(key, self.submitter.clone()) || {
+                    let matched = self.keybindings.handle(Some(view.label.as_str()), key);
+                    let consumed = matched.is_consumed();
+
+                    if let Some(message) = matched.message() {
+                        self.submitter.submit(message).unwrap_or_else(|e| {
+                            tracing::error!("Failed to submit a keybinding message: {}", e);
+                        });
+                    }
+
+                    consumed
+                };
+
+                if consumed {
+                    state.consumed.insert(key.code);
+
+                    return false;
+                }
+            }
+            Event::KeyReleased(key) if state.consumed.remove(&key.code) => return false,
+            _ => {}
+        }
+
+        self.throw_event(Some(surface), event);
+
+        true
+    }
+
+    /// The next time a held key must be repeated, if any.
+    pub(crate) fn next_repeat(&self) -> Option<Instant> {
+        self.keyboards
+            .values()
+            .filter_map(|state| state.repeat.deadline())
+            .min()
+    }
+
+    /// Sends a new press of the held keys that are due. The compositor does not repeat keys, so
+    /// the backend calls it when `State::next_repeat` is reached.
+    pub(crate) fn repeat_keys(&mut self) {
+        let now = Instant::now();
+
+        let mut repeats = Vec::new();
+
+        for state in self.keyboards.values_mut() {
+            let Some(event) = state.repeat.repeat(now, state.modifiers) else {
+                continue;
+            };
+
+            if let Some(focus) = &state.focus {
+                repeats.push((focus.clone(), event));
+            }
+        }
+
+        for (focus, event) in repeats {
+            self.throw_event(Some(focus), Event::KeyPressed(event));
+        }
+    }
+}
+
+/// Converts a keysym to the logical key it stands for.
+fn key(keysym: Keysym) -> Key {
+    let named = match keysym {
+        Keysym::Return | Keysym::KP_Enter => NamedKey::Enter,
+        Keysym::Tab | Keysym::ISO_Left_Tab => NamedKey::Tab,
+        Keysym::space => NamedKey::Space,
+        Keysym::BackSpace => NamedKey::Backspace,
+        Keysym::Escape => NamedKey::Escape,
+        Keysym::Delete => NamedKey::Delete,
+        Keysym::Insert => NamedKey::Insert,
+
+        Keysym::Home => NamedKey::Home,
+        Keysym::End => NamedKey::End,
+        Keysym::Prior => NamedKey::PageUp,
+        Keysym::Next => NamedKey::PageDown,
+
+        Keysym::Up => NamedKey::ArrowUp,
+        Keysym::Down => NamedKey::ArrowDown,
+        Keysym::Left => NamedKey::ArrowLeft,
+        Keysym::Right => NamedKey::ArrowRight,
+
+        Keysym::Shift_L | Keysym::Shift_R => NamedKey::Shift,
+        Keysym::Control_L | Keysym::Control_R => NamedKey::Control,
+        Keysym::Alt_L | Keysym::Alt_R => NamedKey::Alt,
+        Keysym::Super_L | Keysym::Super_R => NamedKey::Super,
+        Keysym::Caps_Lock => NamedKey::CapsLock,
+        Keysym::Num_Lock => NamedKey::NumLock,
+
+        keysym if (Keysym::F1.raw()..=Keysym::F24.raw()).contains(&keysym.raw()) => {
+            NamedKey::F((keysym.raw() - Keysym::F1.raw() + 1) as u8)
+        }
+
+        keysym => {
+            return keysym
+                .key_char()
+                .map(Key::Character)
+                .unwrap_or(Key::Unidentified(keysym.raw()));
+        }
+    };
+
+    Key::Named(named)
+}
+
+fn modifiers(modifiers: XkbModifiers) -> Modifiers {
+    Modifiers {
+        ctrl: modifiers.ctrl,
+        alt: modifiers.alt,
+        shift: modifiers.shift,
+        caps_lock: modifiers.caps_lock,
+        logo: modifiers.logo,
+        num_lock: modifiers.num_lock,
+    }
+}
+
 impl<Message: 'static + Send + Sync> KeyboardHandler for State<Message> {
     fn enter(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _keyboard: &wayland_client::protocol::wl_keyboard::WlKeyboard,
+        keyboard: &WlKeyboard,
         surface: &WlSurface,
-        _serial: u32,
+        serial: u32,
         _raw: &[u32],
         _keysyms: &[Keysym],
     ) {
-        self.throw_event(Some(surface.id()), Event::KeyboardEntered);
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        state.focus = Some(surface.id());
+        state.serial = Some(serial);
+
+        let seat = state.seat;
+
+        self.throw_event(Some(surface.id()), Event::KeyboardEntered { seat });
     }
 
     fn leave(
         &mut self,
         _: &Connection,
         _: &QueueHandle<Self>,
-        _: &WlKeyboard,
+        keyboard: &WlKeyboard,
         surface: &WlSurface,
         _: u32,
     ) {
-        self.throw_event(Some(surface.id()), Event::KeyboardLeaved);
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        // Every held key is released when the focus is lost.
+        state.repeat.stop();
+        state.consumed.clear();
+
+        if state.focus == Some(surface.id()) {
+            state.focus = None;
+        }
+
+        let seat = state.seat;
+
+        self.throw_event(Some(surface.id()), Event::KeyboardLeaved { seat });
     }
 
     fn press_key(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _: &WlKeyboard,
-        _: u32,
-        event: KeyEvent,
+        keyboard: &WlKeyboard,
+        serial: u32,
+        event: XkbKeyEvent,
     ) {
-        self.throw_event(
-            None,
-            Event::KeyPressed {
-                key: event.raw_code,
-            },
-        );
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        state.serial = Some(serial);
+
+        let event = KeyEvent {
+            seat: state.seat,
+            key: key(event.keysym),
+            code: event.raw_code,
+            text: event.utf8,
+            modifiers: state.modifiers,
+            repeat: false,
+        };
+
+        self.record_press(event.seat, serial);
+
+        // A key consumed by a keybinding is not repeated to the widget either.
+        if self.throw_keyboard_event(keyboard, Event::KeyPressed(event.clone()))
+            && let Some(state) = self.keyboards.get_mut(&keyboard.id())
+        {
+            state.repeat.press(&event, Instant::now());
+        }
     }
 
     fn release_key(
         &mut self,
         _: &Connection,
         _: &QueueHandle<Self>,
-        _: &WlKeyboard,
+        keyboard: &WlKeyboard,
         _: u32,
-        event: KeyEvent,
+        event: XkbKeyEvent,
     ) {
-        self.throw_event(
-            None,
-            Event::KeyReleased {
-                key: event.raw_code,
-            },
-        );
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        state.repeat.release(event.raw_code);
+
+        let event = KeyEvent {
+            seat: state.seat,
+            key: key(event.keysym),
+            code: event.raw_code,
+            text: None,
+            modifiers: state.modifiers,
+            repeat: false,
+        };
+
+        self.throw_keyboard_event(keyboard, Event::KeyReleased(event));
     }
 
     fn update_modifiers(
         &mut self,
         _: &Connection,
         _: &QueueHandle<Self>,
-        _: &WlKeyboard,
+        keyboard: &WlKeyboard,
         _serial: u32,
-        modifiers: Modifiers,
+        xkb_modifiers: XkbModifiers,
         _raw_modifiers: RawModifiers,
         _layout: u32,
     ) {
-        self.throw_event(
-            None,
-            Event::KeyModifiersChanged {
-                ctrl: modifiers.ctrl,
-                alt: modifiers.alt,
-                shift: modifiers.shift,
-                caps_lock: modifiers.caps_lock,
-                logo: modifiers.logo,
-                num_lock: modifiers.num_lock,
-            },
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        let modifiers = modifiers(xkb_modifiers);
+
+        state.modifiers = modifiers;
+
+        let seat = state.seat;
+
+        self.throw_keyboard_event(keyboard, Event::KeyModifiersChanged { seat, modifiers });
+    }
+
+    fn update_repeat_info(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        keyboard: &WlKeyboard,
+        info: RepeatInfo,
+    ) {
+        let Some(state) = self.keyboards.get_mut(&keyboard.id()) else {
+            return;
+        };
+
+        state.repeat.configure(match info {
+            RepeatInfo::Repeat { rate, delay } => Some((
+                Duration::from_millis(delay as u64),
+                Duration::from_secs(1) / rate.get(),
+            )),
+            RepeatInfo::Disable => None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key: Key, code: u32) -> KeyEvent {
+        KeyEvent {
+            seat: SeatId::default(),
+            key,
+            code,
+            text: None,
+            modifiers: Modifiers::default(),
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn repeats_after_the_delay_at_the_rate() {
+        let mut repeat = KeyRepeat::default();
+        let now = Instant::now();
+
+        repeat.configure(Some((
+            Duration::from_millis(500),
+            Duration::from_millis(50),
+        )));
+        repeat.press(&press(Key::Character('a'), 30), now);
+
+        assert_eq!(repeat.deadline(), Some(now + Duration::from_millis(500)));
+        assert!(
+            repeat
+                .repeat(now + Duration::from_millis(499), Modifiers::default())
+                .is_none()
         );
+
+        let event = repeat
+            .repeat(now + Duration::from_millis(500), Modifiers::default())
+            .unwrap();
+
+        assert!(event.repeat);
+        assert_eq!(event.code, 30);
+        assert_eq!(repeat.deadline(), Some(now + Duration::from_millis(550)));
+
+        repeat.release(30);
+        assert!(repeat.deadline().is_none());
+    }
+
+    #[test]
+    fn ignores_modifiers_and_other_releases() {
+        let mut repeat = KeyRepeat::default();
+        let now = Instant::now();
+
+        repeat.press(&press(Key::Character('a'), 30), now);
+        repeat.press(&press(Key::Named(NamedKey::Shift), 42), now);
+        repeat.release(42);
+
+        assert_eq!(repeat.deadline(), Some(now + Duration::from_millis(600)));
+
+        repeat.configure(None);
+        assert!(repeat.deadline().is_none());
+
+        repeat.press(&press(Key::Character('a'), 30), now);
+        assert!(repeat.deadline().is_none());
     }
 }
@@ -2,21 +2,170 @@ use crate::prelude::*;
 
 use smithay_client_toolkit::{
     delegate_output,
-    output::{OutputHandler, OutputState},
-    reexports::client::{Connection, QueueHandle, protocol::wl_output::WlOutput},
+    output::{OutputHandler, OutputInfo, OutputState},
+    reexports::client::{
+        Connection, QueueHandle,
+        protocol::wl_output::{self, WlOutput},
+    },
 };
 
 delegate_output!(@<Message: 'static + Send + Sync> State<Message>);
 
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Finds the output with the given key, see `Output::key`. Without a key, or when no output
+    /// has this key, this is the first output advertised by the compositor.
+    pub(crate) fn output(&self, name: Option<&str>) -> Option<WlOutput> {
+        if let Some(name) = name {
+            let output = self.output_state.outputs().find(|output| {
+                self.output_state
+                    .info(output)
+                    .is_some_and(|info| describe(&info).key() == name)
+            });
+
+            if output.is_some() {
+                return output;
+            }
+
+            tracing::warn!("No output named {}, using the default output", name);
+        }
+
+        self.output_state.outputs().next()
+    }
+
+    /// The logical size of the output with the given name, see `State::output`.
+    pub(crate) fn output_size(&self, name: Option<&str>) -> (u32, u32) {
+        let Some(info) = self
+            .output(name)
+            .and_then(|output| self.output_state.info(&output))
+        else {
+            tracing::warn!("No output to resolve relative sizes against");
+
+            return (0, 0);
+        };
+
+        let output = describe(&info);
+
+        (output.width, output.height)
+    }
+
+    /// The description of every output currently advertised by the compositor.
+    pub(crate) fn outputs(&self) -> Vec<Output> {
+        self.output_state
+            .outputs()
+            .filter_map(|output| self.output_state.info(&output))
+            .map(|info| describe(&info))
+            .collect()
+    }
+
+    fn submit_output(&self, output: &WlOutput, event: impl FnOnce(Output) -> ApplicationEvent) {
+        let Some(info) = self.output_state.info(output) else {
+            return;
+        };
+
+        self.events
+            .submit(event(describe(&info)))
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to submit an output event: {}", e);
+            });
+    }
+}
+
+/// Converts the output description of sctk, the logical size falls back to the current mode
+/// divided by the scale factor when the compositor does not provide it.
+fn describe(info: &OutputInfo) -> Output {
+    let (x, y) = info.logical_position.unwrap_or(info.location);
+
+    let (width, height) = info.logical_size.unwrap_or_else(|| {
+        let scale = info.scale_factor.max(1);
+
+        info.modes
+            .iter()
+            .find(|mode| mode.current)
+            .map(|mode| (mode.dimensions.0 / scale, mode.dimensions.1 / scale))
+            .unwrap_or_default()
+    });
+
+    Output {
+        name: info.name.clone().unwrap_or_default(),
+        description: info.description.clone(),
+        make: info.make.clone(),
+        model: info.model.clone(),
+        x,
+        y,
+        width: width.max(0) as u32,
+        height: height.max(0) as u32,
+        scale: info.scale_factor,
+        transform: match info.transform {
+            wl_output::Transform::_90 => Transform::Rotated90,
+            wl_output::Transform::_180 => Transform::Rotated180,
+            wl_output::Transform::_270 => Transform::Rotated270,
+            wl_output::Transform::Flipped => Transform::Flipped,
+            wl_output::Transform::Flipped90 => Transform::Flipped90,
+            wl_output::Transform::Flipped180 => Transform::Flipped180,
+            wl_output::Transform::Flipped270 => Transform::Flipped270,
+            _ => Transform::Normal,
+        },
+        id: info.id,
+    }
+}
+
 impl<Message: 'static + Send + Sync> OutputHandler for State<Message> {
     fn output_state(&mut self) -> &mut OutputState {
         &mut self.output_state
     }
 
-    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        self.submit_output(&output, ApplicationEvent::OutputAdded);
+
+        // Surfaces can only be created by the backend, which instantiates the per output widgets
+        // on this output after the dispatch.
+        if let Some(info) = self.output_state.info(&output) {
+            self.plugged.push(describe(&info));
+        }
+    }
+
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        self.submit_output(&output, ApplicationEvent::OutputChanged);
+
+        let sizes = self
+            .views
+            .iter()
+            .map(|(id, view)| (id.clone(), self.output_size(view.output.as_deref())))
+            .collect::<Vec<_>>();
+
+        for (id, size) in sizes {
+            if let Some(view) = self.views.get_mut(&id) {
+                view.resize(size);
+            }
+        }
+    }
+
+    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        self.submit_output(&output, |output| {
+            ApplicationEvent::OutputRemoved(output.name)
+        });
+
+        let Some(key) = self
+            .output_state
+            .info(&output)
+            .map(|info| describe(&info).key())
+        else {
+            return;
+        };
+
+        self.plugged.retain(|output| output.key() != key);
 
-    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+        let replicas = self
+            .views
+            .values()
+            .filter(|view| {
+                view.replica_of.is_some() && view.output.as_deref() == Some(key.as_str())
+            })
+            .map(|view| view.label.clone())
+            .collect::<Vec<_>>();
 
-    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {
+        for label in replicas {
+            self.destroy(&label);
+        }
     }
 }
@@ -2,53 +2,252 @@ use crate::prelude::*;
 
 use smithay_client_toolkit::{
     delegate_pointer,
-    reexports::client::{Connection, QueueHandle, protocol::wl_pointer::WlPointer},
-    seat::pointer::{PointerEvent, PointerEventKind, PointerHandler},
+    reexports::client::{
+        Connection, QueueHandle,
+        protocol::wl_pointer::{AxisSource, WlPointer},
+    },
+    seat::pointer::{AxisScroll, PointerEvent, PointerEventKind, PointerHandler},
 };
 use wayland_client::Proxy;
 
 delegate_pointer!(@<Message: 'static + Send + Sync> State<Message>);
 
+/// Converts the source of a scroll to the one widgets know, `None` for unknown sources.
+fn scroll_source(source: AxisSource) -> Option<ScrollSource> {
+    match source {
+        AxisSource::Wheel => Some(ScrollSource::Wheel),
+        AxisSource::Finger => Some(ScrollSource::Finger),
+        AxisSource::Continuous => Some(ScrollSource::Continuous),
+        AxisSource::WheelTilt => Some(ScrollSource::WheelTilt),
+        _ => None,
+    }
+}
+
+/// The number of wheel steps of a scroll along one axis. Compositors send high resolution
+/// scrolls, in 120ths of a step, from version 8 of `wl_seat` and discrete steps before. `pending`
+/// keeps the fraction of a step not sent yet.
+fn scroll_steps(axis: &AxisScroll, pending: &mut i32) -> i32 {
+    if axis.value120 == 0 {
+        return axis.discrete;
+    }
+
+    *pending += axis.value120;
+
+    let steps = *pending / 120;
+    *pending -= steps * 120;
+
+    steps
+}
+
+/// Converts the scroll of a pointer frame to the events of the widget, deltas being converted
+/// to buffer pixels with the scale.
+fn scroll_events(
+    seat: SeatId,
+    (x, y): (f64, f64),
+    scale: f64,
+    (horizontal, vertical): (&AxisScroll, &AxisScroll),
+    (steps_x, steps_y): (i32, i32),
+    source: Option<AxisSource>,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    // A frame may only carry the stop of a scroll, without any motion.
+    if horizontal.absolute != 0.0 || vertical.absolute != 0.0 || steps_x != 0 || steps_y != 0 {
+        events.push(Event::PointerScrolled {
+            seat,
+            x,
+            y,
+            delta_x: horizontal.absolute * scale,
+            delta_y: vertical.absolute * scale,
+            steps_x,
+            steps_y,
+            source: source.and_then(scroll_source),
+        });
+    }
+
+    if horizontal.stop || vertical.stop {
+        events.push(Event::PointerScrollStopped {
+            seat,
+            horizontal: horizontal.stop,
+            vertical: vertical.stop,
+        });
+    }
+
+    events
+}
+
 impl<Message: 'static + Send + Sync> PointerHandler for State<Message> {
     fn pointer_frame(
         &mut self,
-        _conn: &Connection,
+        conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _pointer: &WlPointer,
+        pointer: &WlPointer,
         events: &[PointerEvent],
     ) {
-        for event in events {
-            let (x, y) = event.position;
+        let Some(seat) = self.pointer_seat(pointer).map(|seat| seat.id) else {
+            return;
+        };
 
-            self.throw_event(
-                Some(event.surface.id()),
+        for event in events {
+            if let Some(state) = self.pointer_seat(pointer) {
                 match event.kind {
-                    PointerEventKind::Enter { serial: _ } => Event::PointerEntered,
-                    PointerEventKind::Leave { serial: _ } => Event::PointerLeaved,
-                    PointerEventKind::Motion { time: _ } => Event::PointerMoved { x, y },
-                    PointerEventKind::Press {
-                        time: _,
-                        button,
-                        serial: _,
-                    } => Event::PointerPressed { x, y, button },
-                    PointerEventKind::Release {
-                        time: _,
-                        button,
-                        serial: _,
-                    } => Event::PointerReleased { x, y, button },
-                    PointerEventKind::Axis {
-                        time: _,
-                        horizontal,
-                        vertical,
-                        source: _,
-                    } => Event::PointerScrolled {
-                        x,
-                        y,
-                        delta_x: horizontal.absolute,
-                        delta_y: vertical.absolute,
-                    },
-                },
-            );
+                    // The cursor must be set again on every enter.
+                    PointerEventKind::Enter { .. } => {
+                        state.pointer_focus = Some(event.surface.id());
+                        state.cursor = None;
+                    }
+                    PointerEventKind::Leave { .. } => {
+                        if state.pointer_focus == Some(event.surface.id()) {
+                            state.pointer_focus = None;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if let PointerEventKind::Press { serial, .. } = event.kind {
+                self.record_press(seat, serial);
+            }
+
+            // The decorations are not widgets, the backend handles them itself.
+            if let Some(window) = self.frame_owner(&event.surface.id()) {
+                self.frame_pointer_event(conn, pointer, window, event);
+
+                continue;
+            }
+
+            // Positions are sent in logical pixels, widgets expect buffer pixels.
+            let scale = self
+                .views
+                .get(&event.surface.id())
+                .map_or(1.0, |view| view.scale);
+
+            let (x, y) = (event.position.0 * scale, event.position.1 * scale);
+
+            let events = match event.kind {
+                PointerEventKind::Enter { serial: _ } => vec![Event::PointerEntered { seat }],
+                PointerEventKind::Leave { serial: _ } => vec![Event::PointerLeaved { seat }],
+                PointerEventKind::Motion { time: _ } => vec![Event::PointerMoved { seat, x, y }],
+                PointerEventKind::Press {
+                    time: _,
+                    button,
+                    serial: _,
+                } => vec![Event::PointerPressed { seat, x, y, button }],
+                PointerEventKind::Release {
+                    time: _,
+                    button,
+                    serial: _,
+                } => vec![Event::PointerReleased { seat, x, y, button }],
+                PointerEventKind::Axis {
+                    time: _,
+                    horizontal,
+                    vertical,
+                    source,
+                } => {
+                    let steps = match self.pointer_seat(pointer) {
+                        Some(state) => (
+                            scroll_steps(&horizontal, &mut state.scroll_steps.0),
+                            scroll_steps(&vertical, &mut state.scroll_steps.1),
+                        ),
+                        None => (horizontal.discrete, vertical.discrete),
+                    };
+
+                    scroll_events(seat, (x, y), scale, (&horizontal, &vertical), steps, source)
+                }
+            };
+
+            for e in events {
+                self.throw_event(Some(event.surface.id()), e);
+            }
+
+            if let PointerEventKind::Press { .. } = event.kind {
+                self.start_window_action(&event.surface.id());
+            }
         }
+
+        self.update_cursor(conn, pointer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis(absolute: f64, discrete: i32, value120: i32) -> AxisScroll {
+        AxisScroll {
+            absolute,
+            discrete,
+            value120,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_steps_from_either_resolution() {
+        let mut pending = 0;
+
+        assert_eq!(scroll_steps(&axis(15.0, 1, 0), &mut pending), 1);
+        assert_eq!(scroll_steps(&axis(15.0, 0, 120), &mut pending), 1);
+
+        // A high resolution wheel sends fractions of a step.
+        assert_eq!(scroll_steps(&axis(5.0, 0, 60), &mut pending), 0);
+        assert_eq!(scroll_steps(&axis(5.0, 0, 90), &mut pending), 1);
+        assert_eq!(pending, 30);
+        assert_eq!(scroll_steps(&axis(-5.0, 0, -150), &mut pending), -1);
+        assert_eq!(pending, 0);
+
+        assert_eq!(scroll_steps(&axis(3.0, 0, 0), &mut pending), 0);
+    }
+
+    #[test]
+    fn scales_scrolls_and_keeps_their_source() {
+        let events = scroll_events(
+            SeatId(1),
+            (4.0, 6.0),
+            2.0,
+            (&axis(0.0, 0, 0), &axis(15.0, 1, 120)),
+            (0, 1),
+            Some(AxisSource::Wheel),
+        );
+
+        assert_eq!(
+            events,
+            vec![Event::PointerScrolled {
+                seat: SeatId(1),
+                x: 4.0,
+                y: 6.0,
+                delta_x: 0.0,
+                delta_y: 30.0,
+                steps_x: 0,
+                steps_y: 1,
+                source: Some(ScrollSource::Wheel),
+            }]
+        );
+    }
+
+    #[test]
+    fn stops_scrolls() {
+        let stop = AxisScroll {
+            stop: true,
+            ..Default::default()
+        };
+
+        let events = scroll_events(
+            SeatId(1),
+            (0.0, 0.0),
+            1.0,
+            (&AxisScroll::default(), &stop),
+            (0, 0),
+            Some(AxisSource::Finger),
+        );
+
+        assert_eq!(
+            events,
+            vec![Event::PointerScrollStopped {
+                seat: SeatId(1),
+                horizontal: false,
+                vertical: true,
+            }]
+        );
     }
 }
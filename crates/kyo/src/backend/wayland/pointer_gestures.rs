@@ -0,0 +1,333 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::reexports::protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_hold_v1::{self, ZwpPointerGestureHoldV1},
+    zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+    zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+    zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, delegate_noop, protocol::wl_pointer::WlPointer,
+};
+
+delegate_noop!(@<Message: 'static + Send + Sync> State<Message>: ignore ZwpPointerGesturesV1);
+
+/// The touchpad gestures of a pointer. Their events are tagged with the seat, and only their
+/// begin events tell the surface, which receives the whole gesture.
+pub(crate) struct PointerGestures {
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    /// Holds were added in the third version of the protocol.
+    hold: Option<ZwpPointerGestureHoldV1>,
+
+    /// The surface under the pointer when the current gesture began.
+    pub(crate) focus: Option<ObjectId>,
+}
+
+impl PointerGestures {
+    pub(crate) fn new<Message: 'static + Send + Sync>(
+        manager: &ZwpPointerGesturesV1,
+        pointer: &WlPointer,
+        seat: ObjectId,
+        qh: &QueueHandle<State<Message>>,
+    ) -> Self {
+        Self {
+            swipe: manager.get_swipe_gesture(pointer, qh, seat.clone()),
+            pinch: manager.get_pinch_gesture(pointer, qh, seat.clone()),
+            hold: (manager.version() >= 3).then(|| manager.get_hold_gesture(pointer, qh, seat)),
+            focus: None,
+        }
+    }
+
+    pub(crate) fn destroy(&self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+
+        if let Some(hold) = &self.hold {
+            hold.destroy();
+        }
+    }
+}
+
+/// The surface an event of the current gesture goes to, forgotten when the event ends it.
+fn gesture_target<S: Clone>(focus: &mut Option<S>, end: bool) -> Option<S> {
+    if end { focus.take() } else { focus.clone() }
+}
+
+/// Converts an update or the end of a swipe, deltas being converted to buffer pixels with the
+/// scale. The begin event is handled by `State::begin_gesture`.
+fn swipe_event(
+    event: zwp_pointer_gesture_swipe_v1::Event,
+    seat: SeatId,
+    scale: f64,
+) -> Option<Event> {
+    match event {
+        zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => Some(Event::SwipeUpdated {
+            seat,
+            delta_x: dx * scale,
+            delta_y: dy * scale,
+        }),
+        zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => Some(Event::SwipeEnded {
+            seat,
+            cancelled: cancelled != 0,
+        }),
+        _ => None,
+    }
+}
+
+/// Converts an update or the end of a pinch, like `swipe_event`. The scale of the pinch is
+/// relative, so it is kept as is.
+fn pinch_event(
+    event: zwp_pointer_gesture_pinch_v1::Event,
+    seat: SeatId,
+    scale: f64,
+) -> Option<Event> {
+    match event {
+        zwp_pointer_gesture_pinch_v1::Event::Update {
+            dx,
+            dy,
+            scale: pinch,
+            rotation,
+            ..
+        } => Some(Event::PinchUpdated {
+            seat,
+            delta_x: dx * scale,
+            delta_y: dy * scale,
+            scale: pinch,
+            rotation,
+        }),
+        zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => Some(Event::PinchEnded {
+            seat,
+            cancelled: cancelled != 0,
+        }),
+        _ => None,
+    }
+}
+
+/// Converts the end of a hold, like `swipe_event`.
+fn hold_event(event: zwp_pointer_gesture_hold_v1::Event, seat: SeatId) -> Option<Event> {
+    match event {
+        zwp_pointer_gesture_hold_v1::Event::End { cancelled, .. } => Some(Event::HoldEnded {
+            seat,
+            cancelled: cancelled != 0,
+        }),
+        _ => None,
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Starts a gesture of the seat on the surface, and sends it the event.
+    fn begin_gesture(
+        &mut self,
+        seat: &ObjectId,
+        surface: ObjectId,
+        event: impl Fn(SeatId) -> Event,
+    ) {
+        let Some(state) = self.seats.get_mut(seat) else {
+            return;
+        };
+
+        let Some(gestures) = &mut state.gestures else {
+            return;
+        };
+
+        gestures.focus = Some(surface.clone());
+
+        let id = state.id;
+
+        self.throw_event(Some(surface), event(id));
+    }
+
+    /// Sends an event of the current gesture of the seat, built with the seat and the scale of
+    /// the surface. The gesture ends with the event when `end` is set.
+    fn update_gesture(
+        &mut self,
+        seat: &ObjectId,
+        end: bool,
+        event: impl FnOnce(SeatId, f64) -> Option<Event>,
+    ) {
+        let Some(state) = self.seats.get_mut(seat) else {
+            return;
+        };
+
+        let id = state.id;
+
+        let Some(surface) = state
+            .gestures
+            .as_mut()
+            .and_then(|gestures| gesture_target(&mut gestures.focus, end))
+        else {
+            return;
+        };
+
+        let scale = self.views.get(&surface).map_or(1.0, |view| view.scale);
+
+        if let Some(event) = event(id, scale) {
+            self.throw_event(Some(surface), event);
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Dispatch<ZwpPointerGestureSwipeV1, ObjectId>
+    for State<Message>
+{
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        seat: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin {
+                surface, fingers, ..
+            } => state.begin_gesture(seat, surface.id(), |seat| Event::SwipeBegan {
+                seat,
+                fingers,
+            }),
+            event => {
+                let end = matches!(event, zwp_pointer_gesture_swipe_v1::Event::End { .. });
+
+                state.update_gesture(seat, end, |seat, scale| swipe_event(event, seat, scale))
+            }
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Dispatch<ZwpPointerGesturePinchV1, ObjectId>
+    for State<Message>
+{
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        seat: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin {
+                surface, fingers, ..
+            } => state.begin_gesture(seat, surface.id(), |seat| Event::PinchBegan {
+                seat,
+                fingers,
+            }),
+            event => {
+                let end = matches!(event, zwp_pointer_gesture_pinch_v1::Event::End { .. });
+
+                state.update_gesture(seat, end, |seat, scale| pinch_event(event, seat, scale))
+            }
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Dispatch<ZwpPointerGestureHoldV1, ObjectId>
+    for State<Message>
+{
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        seat: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin {
+                surface, fingers, ..
+            } => state.begin_gesture(seat, surface.id(), |seat| Event::HoldBegan {
+                seat,
+                fingers,
+            }),
+            event => {
+                let end = matches!(event, zwp_pointer_gesture_hold_v1::Event::End { .. });
+
+                state.update_gesture(seat, end, |seat, _| hold_event(event, seat))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_the_surface_until_the_gesture_ends() {
+        let mut focus = Some(1);
+
+        assert_eq!(gesture_target(&mut focus, false), Some(1));
+        assert_eq!(gesture_target(&mut focus, true), Some(1));
+        assert_eq!(gesture_target(&mut focus, false), None);
+    }
+
+    #[test]
+    fn scales_swipes() {
+        let update = zwp_pointer_gesture_swipe_v1::Event::Update {
+            time: 0,
+            dx: 3.0,
+            dy: -1.5,
+        };
+
+        assert_eq!(
+            swipe_event(update, SeatId(2), 2.0),
+            Some(Event::SwipeUpdated {
+                seat: SeatId(2),
+                delta_x: 6.0,
+                delta_y: -3.0,
+            })
+        );
+
+        let end = zwp_pointer_gesture_swipe_v1::Event::End {
+            serial: 0,
+            time: 0,
+            cancelled: 1,
+        };
+
+        assert_eq!(
+            swipe_event(end, SeatId(2), 2.0),
+            Some(Event::SwipeEnded {
+                seat: SeatId(2),
+                cancelled: true,
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_the_pinch_scale_and_rotation() {
+        let update = zwp_pointer_gesture_pinch_v1::Event::Update {
+            time: 0,
+            dx: 1.0,
+            dy: 2.0,
+            scale: 1.5,
+            rotation: 10.0,
+        };
+
+        assert_eq!(
+            pinch_event(update, SeatId(0), 2.0),
+            Some(Event::PinchUpdated {
+                seat: SeatId(0),
+                delta_x: 2.0,
+                delta_y: 4.0,
+                scale: 1.5,
+                rotation: 10.0,
+            })
+        );
+
+        let end = zwp_pointer_gesture_hold_v1::Event::End {
+            serial: 0,
+            time: 0,
+            cancelled: 0,
+        };
+
+        assert_eq!(
+            hold_event(end, SeatId(0)),
+            Some(Event::HoldEnded {
+                seat: SeatId(0),
+                cancelled: false,
+            })
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::{
+    delegate_xdg_popup,
+    reexports::{
+        client::{Connection, QueueHandle},
+        protocols::xdg::shell::client::xdg_positioner,
+    },
+    shell::xdg::popup::{Popup, PopupConfigure, PopupHandler},
+};
+use wayland_client::Proxy;
+
+delegate_xdg_popup!(@<Message: 'static + Send + Sync> State<Message>);
+
+/// Converts a direction to the point of the anchor rectangle a popup is placed at.
+pub(crate) fn anchor(direction: Direction) -> xdg_positioner::Anchor {
+    match direction {
+        Direction::None => xdg_positioner::Anchor::None,
+        Direction::Top => xdg_positioner::Anchor::Top,
+        Direction::Bottom => xdg_positioner::Anchor::Bottom,
+        Direction::Left => xdg_positioner::Anchor::Left,
+        Direction::Right => xdg_positioner::Anchor::Right,
+        Direction::TopLeft => xdg_positioner::Anchor::TopLeft,
+        Direction::BottomLeft => xdg_positioner::Anchor::BottomLeft,
+        Direction::TopRight => xdg_positioner::Anchor::TopRight,
+        Direction::BottomRight => xdg_positioner::Anchor::BottomRight,
+    }
+}
+
+/// Converts a direction to the direction a popup extends to from its anchor.
+pub(crate) fn gravity(direction: Direction) -> xdg_positioner::Gravity {
+    match direction {
+        Direction::None => xdg_positioner::Gravity::None,
+        Direction::Top => xdg_positioner::Gravity::Top,
+        Direction::Bottom => xdg_positioner::Gravity::Bottom,
+        Direction::Left => xdg_positioner::Gravity::Left,
+        Direction::Right => xdg_positioner::Gravity::Right,
+        Direction::TopLeft => xdg_positioner::Gravity::TopLeft,
+        Direction::BottomLeft => xdg_positioner::Gravity::BottomLeft,
+        Direction::TopRight => xdg_positioner::Gravity::TopRight,
+        Direction::BottomRight => xdg_positioner::Gravity::BottomRight,
+    }
+}
+
+/// Converts the constraints of a popup to the adjustments the compositor may apply.
+pub(crate) fn constraint_adjustment(
+    constraints: PopupConstraints,
+) -> xdg_positioner::ConstraintAdjustment {
+    [
+        (
+            constraints.flip_x,
+            xdg_positioner::ConstraintAdjustment::FlipX,
+        ),
+        (
+            constraints.flip_y,
+            xdg_positioner::ConstraintAdjustment::FlipY,
+        ),
+        (
+            constraints.slide_x,
+            xdg_positioner::ConstraintAdjustment::SlideX,
+        ),
+        (
+            constraints.slide_y,
+            xdg_positioner::ConstraintAdjustment::SlideY,
+        ),
+        (
+            constraints.resize_x,
+            xdg_positioner::ConstraintAdjustment::ResizeX,
+        ),
+        (
+            constraints.resize_y,
+            xdg_positioner::ConstraintAdjustment::ResizeY,
+        ),
+    ]
+    .into_iter()
+    .filter(|(allowed, _)| *allowed)
+    .fold(
+        xdg_positioner::ConstraintAdjustment::empty(),
+        |adjustment, (_, flag)| adjustment | flag,
+    )
+}
+
+impl<Message: 'static + Send + Sync> PopupHandler for State<Message> {
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        popup: &Popup,
+        config: PopupConfigure,
+    ) {
+        self.throw_event(
+            Some(popup.wl_surface().id()),
+            Event::Configure {
+                width: config.width.max(0) as u32,
+                height: config.height.max(0) as u32,
+            },
+        );
+    }
+
+    fn done(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, popup: &Popup) {
+        let id = popup.wl_surface().id();
+
+        let Some(label) = self.views.get(&id).map(|view| view.label.clone()) else {
+            return;
+        };
+
+        self.events
+            .submit(ApplicationEvent::PopupDismissed { label })
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to submit a popup dismissal: {}", e);
+            });
+
+        self.throw_event(Some(id), Event::Close);
+    }
+}
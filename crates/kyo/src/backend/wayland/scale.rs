@@ -0,0 +1,40 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::reexports::protocols::wp::{
+    fractional_scale::v1::client::{
+        wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+        wp_fractional_scale_v1::{self, WpFractionalScaleV1},
+    },
+    viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::{Connection, Dispatch, QueueHandle, delegate_noop};
+
+delegate_noop!(@<Message: 'static + Send + Sync> State<Message>: ignore WpFractionalScaleManagerV1);
+delegate_noop!(@<Message: 'static + Send + Sync> State<Message>: ignore WpViewporter);
+delegate_noop!(@<Message: 'static + Send + Sync> State<Message>: ignore WpViewport);
+
+/// The fractional scale of a surface is sent in 120ths.
+const FRACTIONAL_SCALE_DENOMINATOR: f64 = 120.0;
+
+impl<Message: 'static + Send + Sync> Dispatch<WpFractionalScaleV1, ObjectId> for State<Message> {
+    fn event(
+        state: &mut Self,
+        _proxy: &WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        id: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event {
+            state.throw_event(
+                Some(id.clone()),
+                Event::ScaleChanged {
+                    scale: scale as f64 / FRACTIONAL_SCALE_DENOMINATOR,
+                },
+            );
+
+            state.update_frame(id);
+        }
+    }
+}
@@ -1,19 +1,169 @@
 use crate::prelude::*;
 
 use smithay_client_toolkit::{
+    compositor::SurfaceData,
+    data_device_manager::data_device::DataDevice,
     delegate_seat,
-    reexports::client::{Connection, QueueHandle, protocol::wl_seat::WlSeat},
-    seat::{Capability, SeatHandler, SeatState},
+    primary_selection::device::PrimarySelectionDevice,
+    reexports::client::{
+        Connection, QueueHandle,
+        protocol::{
+            wl_keyboard::WlKeyboard, wl_pointer::WlPointer, wl_seat::WlSeat, wl_touch::WlTouch,
+        },
+    },
+    seat::{Capability, SeatHandler, SeatState, pointer::ThemedPointer},
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::Proxy;
+
+use super::{
+    cursor::theme_spec, keyboard::KeyboardState, pointer_gestures::PointerGestures,
+    text_input::TextInputState, touch::TouchState,
 };
 
 delegate_seat!(@<Message: 'static + Send + Sync> State<Message>);
 
+/// The input devices of a seat. Every seat has its own devices and focus, so that several people
+/// can use the application at the same time.
+pub(crate) struct Seat {
+    pub(crate) id: SeatId,
+    pub(crate) seat: WlSeat,
+
+    pub(crate) keyboard: Option<WlKeyboard>,
+    pub(crate) pointer: Option<ThemedPointer>,
+    pub(crate) touch: Option<WlTouch>,
+
+    pub(crate) text_input: Option<TextInputState>,
+    pub(crate) gestures: Option<PointerGestures>,
+
+    /// The devices of the selections, released when dropped.
+    pub(crate) data_device: Option<DataDevice>,
+    pub(crate) primary_selection: Option<PrimarySelectionDevice>,
+
+    /// The surface under the pointer, whose widget chooses the cursor.
+    pub(crate) pointer_focus: Option<ObjectId>,
+    /// The cursor last set, `None` when it must be set again.
+    pub(crate) cursor: Option<Cursor>,
+    /// The fractions of a wheel step scrolled but not sent yet, horizontally and vertically.
+    pub(crate) scroll_steps: (i32, i32),
+
+    /// The serial of the last press of a key, a button or a touch point, which the compositor
+    /// requires to grant a grab.
+    pub(crate) serial: Option<u32>,
+}
+
+impl Seat {
+    fn new(id: SeatId, seat: &WlSeat) -> Self {
+        Self {
+            id,
+            seat: seat.clone(),
+
+            keyboard: None,
+            pointer: None,
+            touch: None,
+
+            text_input: None,
+            gestures: None,
+
+            data_device: None,
+            primary_selection: None,
+
+            pointer_focus: None,
+            cursor: None,
+            scroll_steps: (0, 0),
+
+            serial: None,
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// The seat the pointer belongs to.
+    pub(crate) fn pointer_seat(&mut self, pointer: &WlPointer) -> Option<&mut Seat> {
+        self.seats.values_mut().find(|seat| {
+            seat.pointer
+                .as_ref()
+                .is_some_and(|themed| themed.pointer() == pointer)
+        })
+    }
+
+    /// Remembers a press of the seat, so that a popup opened in response can grab the input.
+    pub(crate) fn record_press(&mut self, seat: SeatId, serial: u32) {
+        if let Some((id, state)) = self.seats.iter_mut().find(|(_, state)| state.id == seat) {
+            state.serial = Some(serial);
+
+            self.last_pressed = Some(id.clone());
+        }
+    }
+
+    /// The seat that was pressed last, with the serial of the press.
+    pub(crate) fn last_press(&self) -> Option<(&WlSeat, u32)> {
+        let seat = self.seats.get(self.last_pressed.as_ref()?)?;
+
+        Some((&seat.seat, seat.serial?))
+    }
+
+    /// Releases the device of a seat for a capability, forgetting its state.
+    fn release(&mut self, seat: &WlSeat, capability: Capability) {
+        let Some(seat) = self.seats.get_mut(&seat.id()) else {
+            return;
+        };
+
+        match capability {
+            Capability::Keyboard => {
+                if let Some(text_input) = seat.text_input.take() {
+                    text_input.text_input.destroy();
+                }
+
+                if let Some(keyboard) = seat.keyboard.take() {
+                    self.keyboards.remove(&keyboard.id());
+
+                    keyboard.release();
+                }
+            }
+            Capability::Pointer => {
+                if let Some(gestures) = seat.gestures.take() {
+                    gestures.destroy();
+                }
+
+                // The themed pointer releases the pointer and its cursor surface when dropped.
+                seat.pointer = None;
+                seat.pointer_focus = None;
+                seat.cursor = None;
+            }
+            Capability::Touch => {
+                if let Some(touch) = seat.touch.take() {
+                    self.touches.remove(&touch.id());
+
+                    touch.release();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 impl<Message: 'static + Send + Sync> SeatHandler for State<Message> {
     fn seat_state(&mut self) -> &mut SeatState {
         &mut self.seat_state
     }
 
-    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+    fn new_seat(&mut self, _: &Connection, qh: &QueueHandle<Self>, seat: WlSeat) {
+        let mut state = Seat::new(SeatId(self.next_seat), &seat);
+
+        self.next_seat += 1;
+
+        state.data_device = self
+            .data_device_manager
+            .as_ref()
+            .map(|manager| manager.get_data_device(qh, &seat));
+        state.primary_selection = self
+            .primary_selection_manager
+            .as_ref()
+            .map(|manager| manager.get_selection_device(qh, &seat));
+
+        self.seats.insert(seat.id(), state);
+    }
 
     fn new_capability(
         &mut self,
@@ -22,22 +172,70 @@ impl<Message: 'static + Send + Sync> SeatHandler for State<Message> {
         seat: WlSeat,
         capability: Capability,
     ) {
-        if capability == Capability::Keyboard && self.keyboard.is_none() {
+        let Some(state) = self.seats.get_mut(&seat.id()) else {
+            return;
+        };
+
+        let id = state.id;
+
+        if capability == Capability::Keyboard && state.keyboard.is_none() {
+            // The keyboard loads the compose table of the locale, from `LC_ALL`, `LC_CTYPE` or
+            // `LANG`, so dead keys and compose sequences produce text once completed.
             let keyboard = self
                 .seat_state
                 .get_keyboard(qh, &seat, None)
                 .expect("Failed to create keyboard");
 
-            self.keyboard = Some(keyboard);
+            self.keyboards.insert(keyboard.id(), KeyboardState::new(id));
+
+            state.keyboard = Some(keyboard);
+
+            if let Some(manager) = &self.text_input_manager {
+                state.text_input = Some(TextInputState::new(manager.get_text_input(
+                    &seat,
+                    qh,
+                    seat.id(),
+                )));
+            }
         }
 
-        if capability == Capability::Pointer && self.pointer.is_none() {
+        if capability == Capability::Pointer && state.pointer.is_none() {
+            let surface = self
+                .compositor
+                .create_surface(qh, SurfaceData::new(None, 1));
+
             let pointer = self
                 .seat_state
-                .get_pointer(qh, &seat)
+                .get_pointer_with_theme::<_, SurfaceData>(
+                    qh,
+                    &seat,
+                    self.shm.wl_shm(),
+                    surface,
+                    theme_spec(self.cursor_theme.as_ref()),
+                )
                 .expect("Failed to create pointer");
 
-            self.pointer = Some(pointer);
+            if let Some(manager) = &self.pointer_gestures {
+                state.gestures = Some(PointerGestures::new(
+                    manager,
+                    pointer.pointer(),
+                    seat.id(),
+                    qh,
+                ));
+            }
+
+            state.pointer = Some(pointer);
+        }
+
+        if capability == Capability::Touch && state.touch.is_none() {
+            let touch = self
+                .seat_state
+                .get_touch(qh, &seat)
+                .expect("Failed to create touch");
+
+            self.touches.insert(touch.id(), TouchState::new(id));
+
+            state.touch = Some(touch);
         }
     }
 
@@ -45,17 +243,17 @@ impl<Message: 'static + Send + Sync> SeatHandler for State<Message> {
         &mut self,
         _conn: &Connection,
         _: &QueueHandle<Self>,
-        _: WlSeat,
+        seat: WlSeat,
         capability: Capability,
     ) {
-        if capability == Capability::Keyboard && self.keyboard.is_some() {
-            self.keyboard.take().unwrap().release();
-        }
+        self.release(&seat, capability);
+    }
 
-        if capability == Capability::Pointer && self.pointer.is_some() {
-            self.pointer.take().unwrap().release();
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, seat: WlSeat) {
+        for capability in [Capability::Keyboard, Capability::Pointer, Capability::Touch] {
+            self.release(&seat, capability);
         }
-    }
 
-    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+        self.seats.remove(&seat.id());
+    }
 }
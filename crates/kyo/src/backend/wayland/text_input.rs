@@ -0,0 +1,328 @@
+use crate::prelude::*;
+
+use smithay_client_toolkit::reexports::protocols::wp::text_input::zv3::client::{
+    zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+    zwp_text_input_v3::{self, ZwpTextInputV3},
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, delegate_noop};
+
+delegate_noop!(@<Message: 'static + Send + Sync> State<Message>: ignore ZwpTextInputManagerV3);
+
+/// The longest surrounding text the protocol carries, in bytes.
+const MAX_SURROUNDING: usize = 4000;
+
+/// Trims the surrounding text to the `MAX_SURROUNDING` bytes around the selection, or around the
+/// cursor when the selection is longer, on character boundaries. Returns the trimmed text with
+/// the cursor and the anchor as offsets in it.
+fn trim_surrounding(input: &TextInput) -> (String, usize, usize) {
+    let text = &input.surrounding;
+
+    let cursor = input.cursor.min(text.len());
+    let anchor = input.anchor.min(text.len());
+
+    if text.len() <= MAX_SURROUNDING {
+        return (text.clone(), cursor, anchor);
+    }
+
+    let (low, high) = match (cursor.min(anchor), cursor.max(anchor)) {
+        (low, high) if high - low <= MAX_SURROUNDING => (low, high),
+        _ => (cursor, cursor),
+    };
+
+    let slack = MAX_SURROUNDING - (high - low);
+
+    let mut start = low
+        .saturating_sub(slack / 2)
+        .min(text.len() - MAX_SURROUNDING);
+
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+
+    let mut end = (start + MAX_SURROUNDING).min(text.len());
+
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    (
+        text[start..end].to_string(),
+        cursor.clamp(start, end) - start,
+        anchor.clamp(start, end) - start,
+    )
+}
+
+/// The text input of a seat, which connects the focused widget to the input method.
+pub(crate) struct TextInputState {
+    pub(crate) text_input: ZwpTextInputV3,
+
+    /// The surface the text input entered, which receives the composed text.
+    pub(crate) focus: Option<ObjectId>,
+    /// The state last sent to the compositor, `None` when the text input is disabled.
+    pub(crate) current: Option<TextInput>,
+    /// The number of states committed, which `done` echoes when the input method saw the last
+    /// one.
+    commits: u32,
+    /// Whether the `done` being applied answers an older state, which must then not be changed.
+    outdated: bool,
+
+    /// The events received since the last `done`, applied together.
+    pending_preedit: Option<(String, Option<(usize, usize)>)>,
+    pending_commit: Option<String>,
+    pending_delete: Option<(usize, usize)>,
+}
+
+impl TextInputState {
+    pub(crate) fn new(text_input: ZwpTextInputV3) -> Self {
+        Self {
+            text_input,
+            focus: None,
+            current: None,
+            commits: 0,
+            outdated: false,
+            pending_preedit: None,
+            pending_commit: None,
+            pending_delete: None,
+        }
+    }
+
+    /// Applies the state sent since the last commit.
+    fn commit(&mut self) {
+        self.text_input.commit();
+        self.commits = self.commits.wrapping_add(1);
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Sends the state of the focused widget's text field to the input method, enabling or
+    /// disabling it when the widget starts or stops accepting text input.
+    pub(crate) fn update_text_input(&mut self) {
+        for seat in self.seats.values_mut() {
+            let Some(state) = &mut seat.text_input else {
+                continue;
+            };
+
+            if state.outdated {
+                continue;
+            }
+
+            let Some(view) = state.focus.as_ref().and_then(|id| self.views.get(id)) else {
+                continue;
+            };
+
+            let input = view.widget.text_input();
+
+            if input == state.current {
+                continue;
+            }
+
+            match &input {
+                Some(input) => {
+                    if state.current.is_none() {
+                        state.text_input.enable();
+                    }
+
+                    // The compositor expects a cursor in the surface coordinates.
+                    let rect = input.cursor_rect;
+                    let scale = view.scale;
+
+                    let (surrounding, cursor, anchor) = trim_surrounding(input);
+
+                    state.text_input.set_surrounding_text(
+                        surrounding,
+                        cursor as i32,
+                        anchor as i32,
+                    );
+                    state.text_input.set_cursor_rectangle(
+                        (rect.x as f64 / scale) as i32,
+                        (rect.y as f64 / scale) as i32,
+                        (rect.width as f64 / scale).ceil() as i32,
+                        (rect.height as f64 / scale).ceil() as i32,
+                    );
+                }
+                None => state.text_input.disable(),
+            }
+
+            state.current = input;
+            state.commit();
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> Dispatch<ZwpTextInputV3, ObjectId> for State<Message> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTextInputV3,
+        event: zwp_text_input_v3::Event,
+        seat_id: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(seat) = state.seats.get_mut(seat_id) else {
+            return;
+        };
+
+        let id = seat.id;
+
+        let Some(text_input) = &mut seat.text_input else {
+            return;
+        };
+
+        match event {
+            zwp_text_input_v3::Event::Enter { surface } => {
+                text_input.focus = Some(surface.id());
+                text_input.current = None;
+
+                state.update_text_input();
+            }
+            zwp_text_input_v3::Event::Leave { surface } => {
+                if text_input.focus == Some(surface.id()) {
+                    text_input.focus = None;
+                }
+
+                if text_input.current.take().is_some() {
+                    text_input.text_input.disable();
+                    text_input.commit();
+                }
+            }
+            zwp_text_input_v3::Event::PreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => {
+                // A negative cursor hides it.
+                let cursor = (cursor_begin >= 0 && cursor_end >= 0)
+                    .then_some((cursor_begin as usize, cursor_end as usize));
+
+                text_input.pending_preedit = Some((text.unwrap_or_default(), cursor));
+            }
+            zwp_text_input_v3::Event::CommitString { text } => {
+                text_input.pending_commit = text;
+            }
+            zwp_text_input_v3::Event::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => {
+                text_input.pending_delete = Some((before_length as usize, after_length as usize));
+            }
+            zwp_text_input_v3::Event::Done { serial } => {
+                let Some(focus) = text_input.focus.clone() else {
+                    return;
+                };
+
+                // The changes are still applied when the input method answers an older state,
+                // but the state must not be sent again in response.
+                text_input.outdated = serial != text_input.commits;
+
+                // The preedit text is replaced on every `done`, even when none was sent.
+                let (text, cursor) = text_input.pending_preedit.take().unwrap_or_default();
+                let commit = text_input.pending_commit.take();
+                let delete = text_input.pending_delete.take();
+
+                // The protocol removes the previous preedit text before deleting the surrounding
+                // text and inserting the committed text, the new one is shown last.
+                let cleared = delete.is_some() || commit.is_some();
+
+                if cleared {
+                    state.throw_event(
+                        Some(focus.clone()),
+                        Event::Preedit {
+                            seat: id,
+                            text: String::new(),
+                            cursor: None,
+                        },
+                    );
+                }
+
+                if let Some((before, after)) = delete {
+                    state.throw_event(
+                        Some(focus.clone()),
+                        Event::DeleteSurrounding {
+                            seat: id,
+                            before,
+                            after,
+                        },
+                    );
+                }
+
+                if let Some(text) = commit {
+                    state.throw_event(Some(focus.clone()), Event::Commit { seat: id, text });
+                }
+
+                if !(cleared && text.is_empty()) {
+                    state.throw_event(
+                        Some(focus),
+                        Event::Preedit {
+                            seat: id,
+                            text,
+                            cursor,
+                        },
+                    );
+                }
+
+                if let Some(text_input) = state
+                    .seats
+                    .get_mut(seat_id)
+                    .and_then(|seat| seat.text_input.as_mut())
+                {
+                    text_input.outdated = false;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(surrounding: String, cursor: usize, anchor: usize) -> TextInput {
+        TextInput {
+            surrounding,
+            cursor,
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_short_text() {
+        let (text, cursor, anchor) = trim_surrounding(&input("hello".to_string(), 5, 1));
+
+        assert_eq!((text.as_str(), cursor, anchor), ("hello", 5, 1));
+    }
+
+    #[test]
+    fn trims_around_the_cursor() {
+        let text = "a".repeat(3000) + &"é".repeat(3000);
+        let cursor = 3000 + 2 * 1500;
+
+        let (trimmed, shifted, anchor) = trim_surrounding(&input(text.clone(), cursor, cursor));
+
+        assert!(trimmed.len() <= MAX_SURROUNDING);
+        assert_eq!(shifted, anchor);
+        assert_eq!(&trimmed[..shifted], &text[cursor - shifted..cursor]);
+        assert_eq!(
+            &trimmed[shifted..],
+            &text[cursor..cursor + trimmed.len() - shifted]
+        );
+    }
+
+    #[test]
+    fn keeps_the_selection() {
+        let text = "é".repeat(5000);
+
+        let (trimmed, cursor, anchor) = trim_surrounding(&input(text.clone(), 9000, 6000));
+
+        assert!(trimmed.len() <= MAX_SURROUNDING);
+        assert_eq!(cursor - anchor, 3000);
+
+        let (trimmed, cursor, anchor) = trim_surrounding(&input(text, 10000, 0));
+
+        assert!(trimmed.len() <= MAX_SURROUNDING);
+        assert_eq!(cursor, trimmed.len());
+        assert_eq!(anchor, 0);
+    }
+}
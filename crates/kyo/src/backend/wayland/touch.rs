@@ -0,0 +1,477 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::prelude::*;
+
+use smithay_client_toolkit::{
+    delegate_touch,
+    reexports::client::{
+        Connection, QueueHandle,
+        protocol::{wl_surface::WlSurface, wl_touch::WlTouch},
+    },
+    seat::touch::TouchHandler,
+};
+use wayland_backend::client::ObjectId;
+use wayland_client::Proxy;
+
+delegate_touch!(@<Message: 'static + Send + Sync> State<Message>);
+
+/// The left button of a pointer, pressed by the emulated pointer.
+const BTN_LEFT: u32 = 0x110;
+
+/// A touch point currently on a surface.
+pub(crate) struct TouchPoint<S> {
+    /// The surface the point went down on, which receives all its events.
+    pub(crate) surface: S,
+    /// The last position of the point, in buffer pixels.
+    pub(crate) position: (f64, f64),
+}
+
+/// The state of a touch device, tracked across its events. It turns them into the events to send
+/// to each surface, `S` identifying the surfaces.
+pub(crate) struct TouchState<S = ObjectId> {
+    pub(crate) seat: SeatId,
+
+    /// The touch points down, by id.
+    pub(crate) points: HashMap<i32, TouchPoint<S>>,
+    /// The touch point emulating a pointer, on a widget that does not handle touch events.
+    pub(crate) primary: Option<i32>,
+}
+
+impl<S: Clone + Eq + Hash> TouchState<S> {
+    pub(crate) fn new(seat: SeatId) -> Self {
+        Self {
+            seat,
+            points: HashMap::new(),
+            primary: None,
+        }
+    }
+
+    /// A point went down on a surface. Only the first point drives the emulated pointer, the
+    /// others are ignored on widgets that do not handle touch events.
+    fn down(
+        &mut self,
+        id: i32,
+        surface: S,
+        (x, y): (f64, f64),
+        accepts_touch: bool,
+    ) -> Vec<(S, Event)> {
+        let seat = self.seat;
+
+        self.points.insert(
+            id,
+            TouchPoint {
+                surface: surface.clone(),
+                position: (x, y),
+            },
+        );
+
+        if accepts_touch {
+            return vec![(surface, Event::TouchDown { seat, id, x, y })];
+        }
+
+        if self.primary.is_some() {
+            return Vec::new();
+        }
+
+        self.primary = Some(id);
+
+        [
+            Event::PointerEntered { seat },
+            Event::PointerMoved { seat, x, y },
+            Event::PointerPressed {
+                seat,
+                x,
+                y,
+                button: BTN_LEFT,
+            },
+        ]
+        .into_iter()
+        .map(|event| (surface.clone(), event))
+        .collect()
+    }
+
+    /// A point was lifted.
+    fn up(&mut self, id: i32, accepts_touch: impl Fn(&S) -> bool) -> Vec<(S, Event)> {
+        let seat = self.seat;
+
+        let Some(point) = self.points.remove(&id) else {
+            return Vec::new();
+        };
+
+        let is_primary = self.primary == Some(id);
+
+        if is_primary {
+            self.primary = None;
+        }
+
+        if accepts_touch(&point.surface) {
+            return vec![(point.surface, Event::TouchUp { seat, id })];
+        }
+
+        if !is_primary {
+            return Vec::new();
+        }
+
+        let (x, y) = point.position;
+
+        vec![
+            (
+                point.surface.clone(),
+                Event::PointerReleased {
+                    seat,
+                    x,
+                    y,
+                    button: BTN_LEFT,
+                },
+            ),
+            (point.surface, Event::PointerLeaved { seat }),
+        ]
+    }
+
+    /// A point moved, to a position in the buffer pixels of its surface.
+    fn motion(&mut self, id: i32, (x, y): (f64, f64), accepts_touch: bool) -> Vec<(S, Event)> {
+        let seat = self.seat;
+        let is_primary = self.primary == Some(id);
+
+        let Some(point) = self.points.get_mut(&id) else {
+            return Vec::new();
+        };
+
+        point.position = (x, y);
+
+        if accepts_touch {
+            vec![(point.surface.clone(), Event::TouchMoved { seat, id, x, y })]
+        } else if is_primary {
+            vec![(point.surface.clone(), Event::PointerMoved { seat, x, y })]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The compositor took over every point. The emulated pointer cancels its press rather than
+    /// releasing it, as nothing was clicked, and each surface handling touch events is told once.
+    fn cancel(&mut self, accepts_touch: impl Fn(&S) -> bool) -> Vec<(S, Event)> {
+        let seat = self.seat;
+        let primary = self.primary.take();
+        let mut points = std::mem::take(&mut self.points);
+
+        let mut events = Vec::new();
+
+        if let Some(point) = primary.and_then(|id| points.remove(&id)) {
+            events.push((point.surface.clone(), Event::PointerCancelled { seat }));
+            events.push((point.surface, Event::PointerLeaved { seat }));
+        }
+
+        let surfaces = points
+            .into_values()
+            .map(|point| point.surface)
+            .filter(|surface| accepts_touch(surface))
+            .collect::<HashSet<_>>();
+
+        events.extend(
+            surfaces
+                .into_iter()
+                .map(|surface| (surface, Event::TouchCancelled { seat })),
+        );
+
+        events
+    }
+
+    /// Forgets the points of a destroyed surface.
+    pub(crate) fn forget(&mut self, surface: &S) {
+        self.points.retain(|_, point| point.surface != *surface);
+
+        if self
+            .primary
+            .is_some_and(|primary| !self.points.contains_key(&primary))
+        {
+            self.primary = None;
+        }
+    }
+}
+
+/// Whether the widget of the surface handles touch events, otherwise they are turned into pointer
+/// events.
+fn accepts_touch<Message: 'static + Send + Sync>(
+    views: &HashMap<ObjectId, WaylandWidget<Message>>,
+    surface: &ObjectId,
+) -> bool {
+    views
+        .get(surface)
+        .is_some_and(|view| view.widget.accepts_touch())
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// Converts a position from logical pixels to the buffer pixels of the surface.
+    fn touch_position(&self, surface: &ObjectId, position: (f64, f64)) -> (f64, f64) {
+        let scale = self.views.get(surface).map_or(1.0, |view| view.scale);
+
+        (position.0 * scale, position.1 * scale)
+    }
+
+    fn throw_touch_events(&mut self, events: Vec<(ObjectId, Event)>) {
+        for (surface, event) in events {
+            self.throw_event(Some(surface), event);
+        }
+    }
+}
+
+impl<Message: 'static + Send + Sync> TouchHandler for State<Message> {
+    fn down(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        serial: u32,
+        _time: u32,
+        surface: WlSurface,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        if let Some(seat) = self.touches.get(&touch.id()).map(|state| state.seat) {
+            self.record_press(seat, serial);
+        }
+
+        let surface = surface.id();
+        let position = self.touch_position(&surface, position);
+        let accepts_touch = accepts_touch(&self.views, &surface);
+
+        let Some(state) = self.touches.get_mut(&touch.id()) else {
+            return;
+        };
+
+        let events = state.down(id, surface.clone(), position, accepts_touch);
+
+        if events.is_empty() {
+            return;
+        }
+
+        self.throw_touch_events(events);
+        self.start_window_action(&surface);
+    }
+
+    fn up(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        _serial: u32,
+        _time: u32,
+        id: i32,
+    ) {
+        let Some(state) = self.touches.get_mut(&touch.id()) else {
+            return;
+        };
+
+        let views = &self.views;
+        let events = state.up(id, |surface| accepts_touch(views, surface));
+
+        self.throw_touch_events(events);
+    }
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        _time: u32,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let Some(surface) = self
+            .touches
+            .get(&touch.id())
+            .and_then(|state| state.points.get(&id))
+            .map(|point| point.surface.clone())
+        else {
+            return;
+        };
+
+        let position = self.touch_position(&surface, position);
+        let accepts_touch = accepts_touch(&self.views, &surface);
+
+        let Some(state) = self.touches.get_mut(&touch.id()) else {
+            return;
+        };
+
+        let events = state.motion(id, position, accepts_touch);
+
+        self.throw_touch_events(events);
+    }
+
+    fn shape(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _id: i32,
+        _major: f64,
+        _minor: f64,
+    ) {
+    }
+
+    fn orientation(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _id: i32,
+        _orientation: f64,
+    ) {
+    }
+
+    fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, touch: &WlTouch) {
+        let Some(state) = self.touches.get_mut(&touch.id()) else {
+            return;
+        };
+
+        let views = &self.views;
+        let events = state.cancel(|surface| accepts_touch(views, surface));
+
+        self.throw_touch_events(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> TouchState<u32> {
+        TouchState::new(SeatId::default())
+    }
+
+    fn seat() -> SeatId {
+        SeatId::default()
+    }
+
+    #[test]
+    fn emulates_a_pointer_with_the_first_point() {
+        let mut state = state();
+
+        assert_eq!(
+            state.down(0, 1, (10.0, 20.0), false),
+            vec![
+                (1, Event::PointerEntered { seat: seat() }),
+                (
+                    1,
+                    Event::PointerMoved {
+                        seat: seat(),
+                        x: 10.0,
+                        y: 20.0
+                    }
+                ),
+                (
+                    1,
+                    Event::PointerPressed {
+                        seat: seat(),
+                        x: 10.0,
+                        y: 20.0,
+                        button: BTN_LEFT
+                    }
+                ),
+            ]
+        );
+
+        // A second point does not drive the pointer.
+        assert!(state.down(1, 1, (0.0, 0.0), false).is_empty());
+        assert!(state.motion(1, (5.0, 5.0), false).is_empty());
+        assert!(state.up(1, |_| false).is_empty());
+
+        assert_eq!(
+            state.motion(0, (12.0, 20.0), false),
+            vec![(
+                1,
+                Event::PointerMoved {
+                    seat: seat(),
+                    x: 12.0,
+                    y: 20.0
+                }
+            )]
+        );
+        assert_eq!(
+            state.up(0, |_| false),
+            vec![
+                (
+                    1,
+                    Event::PointerReleased {
+                        seat: seat(),
+                        x: 12.0,
+                        y: 20.0,
+                        button: BTN_LEFT
+                    }
+                ),
+                (1, Event::PointerLeaved { seat: seat() }),
+            ]
+        );
+        assert_eq!(state.primary, None);
+    }
+
+    #[test]
+    fn sends_touch_events_to_widgets_handling_them() {
+        let mut state = state();
+
+        assert_eq!(
+            state.down(3, 1, (1.0, 2.0), true),
+            vec![(
+                1,
+                Event::TouchDown {
+                    seat: seat(),
+                    id: 3,
+                    x: 1.0,
+                    y: 2.0
+                }
+            )]
+        );
+        assert_eq!(state.primary, None);
+        assert_eq!(
+            state.up(3, |_| true),
+            vec![(
+                1,
+                Event::TouchUp {
+                    seat: seat(),
+                    id: 3
+                }
+            )]
+        );
+        assert!(state.up(3, |_| true).is_empty());
+    }
+
+    #[test]
+    fn cancels_the_emulated_press() {
+        let mut state = state();
+
+        state.down(0, 1, (0.0, 0.0), false);
+        state.down(1, 2, (0.0, 0.0), true);
+        state.down(2, 2, (0.0, 0.0), true);
+
+        let events = state.cancel(|surface| *surface == 2);
+
+        assert_eq!(
+            events,
+            vec![
+                (1, Event::PointerCancelled { seat: seat() }),
+                (1, Event::PointerLeaved { seat: seat() }),
+                (2, Event::TouchCancelled { seat: seat() }),
+            ]
+        );
+        assert!(state.points.is_empty());
+        assert_eq!(state.primary, None);
+    }
+
+    #[test]
+    fn forgets_the_points_of_a_destroyed_surface() {
+        let mut state = state();
+
+        state.down(0, 1, (0.0, 0.0), false);
+        state.down(1, 2, (0.0, 0.0), true);
+
+        state.forget(&1);
+
+        assert_eq!(state.primary, None);
+        assert_eq!(state.points.len(), 1);
+        assert!(state.motion(0, (1.0, 1.0), false).is_empty());
+    }
+}
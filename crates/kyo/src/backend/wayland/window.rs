@@ -1,17 +1,131 @@
 use smithay_client_toolkit::{
     delegate_xdg_shell, delegate_xdg_window,
+    reexports::csd_frame,
     shell::{
         WaylandSurface,
-        xdg::window::{Window, WindowConfigure, WindowHandler},
+        xdg::window::{DecorationMode, Window, WindowConfigure, WindowHandler},
     },
 };
+use wayland_backend::client::ObjectId;
 use wayland_client::{Connection, Proxy, QueueHandle};
 
 use crate::prelude::*;
 
+use super::decorations::resize_edge;
+
 delegate_xdg_shell!(@<Message: 'static + Send + Sync> State<Message>);
 delegate_xdg_window!(@<Message: 'static + Send + Sync> State<Message>);
 
+/// Converts the state of a configure to the one sent to the application.
+fn window_state(configure: &WindowConfigure) -> WindowState {
+    WindowState {
+        activated: configure.is_activated(),
+        maximized: configure.is_maximized(),
+        fullscreen: configure.is_fullscreen(),
+        resizing: configure.is_resizing(),
+        suspended: configure.state.contains(csd_frame::WindowState::SUSPENDED),
+        tiled: Edges {
+            top: configure.is_tiled_top(),
+            bottom: configure.is_tiled_bottom(),
+            left: configure.is_tiled_left(),
+            right: configure.is_tiled_right(),
+        },
+        bounds: configure.suggested_bounds,
+    }
+}
+
+impl<Message: 'static + Send + Sync> State<Message> {
+    /// The window tracked under `label`, if it is one.
+    pub(crate) fn window(&self, label: &str) -> Option<&Window> {
+        self.lut
+            .get(label)
+            .and_then(|id| self.views.get(id))
+            .and_then(|view| view.surface.window())
+    }
+
+    /// Starts the interactive operation the widget of a surface asked for while handling the
+    /// last press, which the compositor needs to authorize it.
+    pub(crate) fn start_window_action(&mut self, id: &ObjectId) {
+        let Some(action) = self
+            .views
+            .get_mut(id)
+            .and_then(|view| view.widget.window_action())
+        else {
+            return;
+        };
+
+        let Some((seat, serial)) = self.last_press() else {
+            tracing::warn!("No press to start {:?} with", action);
+
+            return;
+        };
+
+        let Some(view) = self.views.get(id) else {
+            return;
+        };
+
+        let Some(window) = view.surface.window() else {
+            tracing::warn!("Only windows can start {:?}", action);
+
+            return;
+        };
+
+        match action {
+            WindowAction::Move => window.move_(seat, serial),
+            WindowAction::Resize(edges) => window.resize(seat, serial, resize_edge(edges)),
+            // The menu is placed in logical pixels.
+            WindowAction::ShowMenu { x, y } => window.show_window_menu(
+                seat,
+                serial,
+                ((x / view.scale) as i32, (y / view.scale) as i32),
+            ),
+        }
+    }
+
+    /// Sends a request of the application about the window tracked under `label`.
+    pub(crate) fn request_window(&mut self, label: &str, request: WindowRequest) {
+        let Some(window) = self.window(label) else {
+            tracing::warn!("No window labelled {} for {:?}", label, request);
+
+            return;
+        };
+
+        let retitled = match &request {
+            WindowRequest::SetTitle(title) => Some(title.clone()),
+            _ => None,
+        };
+
+        match request {
+            WindowRequest::SetTitle(title) => window.set_title(title),
+            WindowRequest::SetAppId(app_id) => window.set_app_id(app_id),
+            WindowRequest::SetMinSize(size) => window.set_min_size(size),
+            WindowRequest::SetMaxSize(size) => window.set_max_size(size),
+            WindowRequest::SetParent(parent) => {
+                window.set_parent(parent.as_deref().and_then(|parent| self.window(parent)))
+            }
+            WindowRequest::Maximize => window.set_maximized(),
+            WindowRequest::Unmaximize => window.unset_maximized(),
+            WindowRequest::Fullscreen(output) => {
+                window.set_fullscreen(self.output(output.as_deref()).as_ref())
+            }
+            WindowRequest::Unfullscreen => window.unset_fullscreen(),
+            WindowRequest::Minimize => window.set_minimized(),
+        }
+
+        // The size limits are double buffered.
+        window.commit();
+
+        // The decorations draw the title.
+        if let (Some(title), Some(id)) = (retitled, self.lut.get(label).cloned()) {
+            if let Some(view) = self.views.get_mut(&id) {
+                view.title = Some(title);
+            }
+
+            self.update_frame(&id);
+        }
+    }
+}
+
 impl<Message: 'static + Send + Sync> WindowHandler for State<Message> {
     fn request_close(&mut self, _: &Connection, _: &QueueHandle<Self>, window: &Window) {
         self.throw_event(Some(window.wl_surface().id()), Event::Close);
@@ -20,17 +134,52 @@ impl<Message: 'static + Send + Sync> WindowHandler for State<Message> {
     fn configure(
         &mut self,
         _conn: &Connection,
-        _qh: &QueueHandle<Self>,
+        qh: &QueueHandle<Self>,
         window: &Window,
         configure: WindowConfigure,
         _serial: u32,
     ) {
+        let id = window.wl_surface().id();
+        let state = window_state(&configure);
+
+        if let Some(view) = self.views.get(&id) {
+            self.events
+                .submit(ApplicationEvent::WindowConfigured {
+                    label: view.label.clone(),
+                    state,
+                })
+                .unwrap_or_else(|e| {
+                    tracing::error!("Failed to submit a window configure: {}", e);
+                });
+        }
+
+        // The compositor falls back to client side decorations when it cannot draw them.
+        let titlebar = match &self.decorations {
+            Some(theme)
+                if configure.decoration_mode == DecorationMode::Client && !state.fullscreen =>
+            {
+                theme.titlebar_height
+            }
+            _ => 0,
+        };
+
+        self.decorate(&id, titlebar > 0, state, qh);
+
+        // The configured size includes the titlebar, which is not part of the widget.
+        let height = configure
+            .new_size
+            .1
+            .map(|n| n.get().saturating_sub(titlebar))
+            .unwrap_or(0);
+
         self.throw_event(
-            Some(window.wl_surface().id()),
+            Some(id.clone()),
             Event::Configure {
                 width: configure.new_size.0.map(|n| n.get()).unwrap_or(0),
-                height: configure.new_size.1.map(|n| n.get()).unwrap_or(0),
+                height,
             },
         );
+
+        self.update_frame(&id);
     }
 }
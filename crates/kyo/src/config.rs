@@ -0,0 +1,159 @@
+//! This module defines the configuration of the `WaylandBackend`.
+
+use wgpu::{Backends, Limits, PowerPreference, PresentMode};
+
+use crate::prelude::*;
+
+/// What to do when the requested adapter is not available.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterFallback {
+    /// Fail to start the backend.
+    None,
+
+    /// Use the adapter matching the power preference.
+    #[default]
+    PowerPreference,
+
+    /// Use the adapter matching the power preference, or a software adapter if there is no
+    /// hardware adapter.
+    Software,
+}
+
+/// A cursor theme, loaded from the `XCURSOR_PATH` directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorTheme {
+    pub name: String,
+
+    /// The size of the cursors in logical pixels, they are loaded larger on scaled outputs.
+    pub size: u32,
+}
+
+/// The configuration of the `WaylandBackend`, provided to `Application::run_with`.
+/// Example usage:
+///
+/// ```rust
+/// use kyo::prelude::{reexport::*, *};
+///
+/// let config = WaylandConfig::default()
+///     .power_preference(PowerPreference::HighPerformance)
+///     .layer(Layer::Overlay);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WaylandConfig {
+    /// The wgpu backends allowed to be used.
+    pub backends: Backends,
+
+    /// The name of the adapter to use, matched case-insensitively against a part of the adapter
+    /// name. When `None`, the adapter is chosen with the power preference.
+    pub adapter: Option<String>,
+    pub power_preference: PowerPreference,
+    pub fallback: AdapterFallback,
+
+    /// The limits requested for the device.
+    pub limits: Limits,
+
+    /// The present modes to use, in order of preference. Fifo is used if none is supported.
+    pub present_modes: Vec<PresentMode>,
+
+    /// The layer of the surfaces that are not windows.
+    pub layer: Layer,
+
+    /// The cursor theme used when the compositor cannot draw the cursors itself. When `None`, it
+    /// is read from `XCURSOR_THEME` and `XCURSOR_SIZE`.
+    pub cursor_theme: Option<CursorTheme>,
+
+    /// The timings and distances of the gestures recognized from the pointer events.
+    pub gestures: GestureConfig,
+
+    /// The decorations drawn around windows when the compositor does not draw them. When
+    /// `None`, such windows are left without decorations.
+    pub decorations: Option<DecorationTheme>,
+}
+
+impl Default for WaylandConfig {
+    fn default() -> Self {
+        Self {
+            backends: Backends::all(),
+
+            adapter: None,
+            power_preference: PowerPreference::LowPower,
+            fallback: AdapterFallback::default(),
+
+            limits: Limits::default(),
+
+            present_modes: vec![
+                PresentMode::Mailbox,
+                PresentMode::FifoRelaxed,
+                PresentMode::Fifo,
+            ],
+
+            layer: Layer::default(),
+
+            cursor_theme: None,
+
+            gestures: GestureConfig::default(),
+
+            decorations: Some(DecorationTheme::default()),
+        }
+    }
+}
+
+impl WaylandConfig {
+    pub fn backends(self, backends: Backends) -> Self {
+        Self { backends, ..self }
+    }
+
+    pub fn adapter(self, adapter: impl Into<String>) -> Self {
+        Self {
+            adapter: Some(adapter.into()),
+            ..self
+        }
+    }
+
+    pub fn power_preference(self, power_preference: PowerPreference) -> Self {
+        Self {
+            power_preference,
+            ..self
+        }
+    }
+
+    pub fn fallback(self, fallback: AdapterFallback) -> Self {
+        Self { fallback, ..self }
+    }
+
+    pub fn limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub fn present_modes(self, present_modes: impl Into<Vec<PresentMode>>) -> Self {
+        Self {
+            present_modes: present_modes.into(),
+            ..self
+        }
+    }
+
+    pub fn layer(self, layer: Layer) -> Self {
+        Self { layer, ..self }
+    }
+
+    pub fn cursor_theme(self, name: impl Into<String>, size: u32) -> Self {
+        Self {
+            cursor_theme: Some(CursorTheme {
+                name: name.into(),
+                size,
+            }),
+            ..self
+        }
+    }
+
+    pub fn gestures(self, gestures: GestureConfig) -> Self {
+        Self { gestures, ..self }
+    }
+
+    pub fn decorations(self, decorations: Option<DecorationTheme>) -> Self {
+        Self {
+            decorations,
+            ..self
+        }
+    }
+}
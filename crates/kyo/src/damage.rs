@@ -0,0 +1,70 @@
+use crate::prelude::*;
+
+/// Accumulates the damage of a surface between two frames.
+///
+/// Widgets are drawn into a persistent target texture that is copied to the swapchain, so the
+/// target always holds the last frame and only the damage since then has to be repainted,
+/// whatever buffer the swapchain hands out.
+#[derive(Default)]
+pub(crate) struct DamageTracker {
+    pending: Damage,
+}
+
+impl DamageTracker {
+    /// Forgets the content of the target, e.g. after the surface was reconfigured.
+    pub(crate) fn reset(&mut self) {
+        self.pending = Damage::Full;
+    }
+
+    pub(crate) fn add(&mut self, damage: Damage) {
+        self.pending.merge(damage);
+    }
+
+    pub(crate) fn is_dirty(&self) -> bool {
+        !self.pending.is_none()
+    }
+
+    /// Takes the damage to repaint for a new frame.
+    pub(crate) fn take(&mut self) -> Damage {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32) -> Damage {
+        Damage::Regions(vec![Rect::new(x, 0, 1, 1)])
+    }
+
+    #[test]
+    fn accumulates_damage_until_taken() {
+        let mut tracker = DamageTracker::default();
+        assert!(!tracker.is_dirty());
+
+        tracker.add(region(0));
+        tracker.add(region(1));
+        assert!(tracker.is_dirty());
+
+        assert_eq!(
+            tracker.take().rects(8, 8),
+            vec![Rect::new(0, 0, 1, 1), Rect::new(1, 0, 1, 1)]
+        );
+        assert!(!tracker.is_dirty());
+        assert_eq!(tracker.take(), Damage::None);
+    }
+
+    #[test]
+    fn repaints_everything_after_a_reset() {
+        let mut tracker = DamageTracker::default();
+
+        tracker.add(region(0));
+        tracker.take();
+
+        tracker.reset();
+        tracker.add(region(1));
+        assert!(tracker.is_dirty());
+        assert_eq!(tracker.take(), Damage::Full);
+    }
+}
@@ -0,0 +1,285 @@
+//! This module defines the theme of the decorations that kyo draws around windows when the
+//! compositor leaves them to the client, e.g. on GNOME.
+
+use std::{fmt, sync::Arc};
+
+use crate::prelude::*;
+
+mod font;
+
+/// The colors of the decorations, as RGBA without premultiplied alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecorationColors {
+    pub background: [u8; 4],
+    /// The color of the title and of the button icons.
+    pub foreground: [u8; 4],
+
+    /// The background of the button under the pointer.
+    pub hovered: [u8; 4],
+    pub close_hovered: [u8; 4],
+}
+
+/// Draws the title of a window on its titlebar, instead of the small bitmap font kyo draws it
+/// with, e.g. to use the fonts of the application.
+pub type TitlePainter = Arc<dyn Fn(&mut TitlebarCanvas<'_>) + Send + Sync>;
+
+/// The theme of the decorations drawn by kyo.
+#[derive(Clone)]
+pub struct DecorationTheme {
+    /// The height of the titlebar, in logical pixels.
+    pub titlebar_height: u32,
+    /// The width of the invisible border around the window that resizes it, in logical pixels.
+    pub resize_border: u32,
+
+    /// The colors of the focused window.
+    pub active: DecorationColors,
+    /// The colors of the other windows.
+    pub inactive: DecorationColors,
+
+    pub title_painter: Option<TitlePainter>,
+}
+
+impl Default for DecorationTheme {
+    fn default() -> Self {
+        Self {
+            titlebar_height: 32,
+            resize_border: 8,
+
+            active: DecorationColors {
+                background: [0x30, 0x30, 0x30, 0xff],
+                foreground: [0xff, 0xff, 0xff, 0xff],
+                hovered: [0x48, 0x48, 0x48, 0xff],
+                close_hovered: [0xc0, 0x1c, 0x28, 0xff],
+            },
+            inactive: DecorationColors {
+                background: [0x24, 0x24, 0x24, 0xff],
+                foreground: [0x90, 0x90, 0x90, 0xff],
+                hovered: [0x3c, 0x3c, 0x3c, 0xff],
+                close_hovered: [0xc0, 0x1c, 0x28, 0xff],
+            },
+
+            title_painter: None,
+        }
+    }
+}
+
+impl fmt::Debug for DecorationTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecorationTheme")
+            .field("titlebar_height", &self.titlebar_height)
+            .field("resize_border", &self.resize_border)
+            .field("active", &self.active)
+            .field("inactive", &self.inactive)
+            .field("title_painter", &self.title_painter.is_some())
+            .finish()
+    }
+}
+
+impl DecorationTheme {
+    pub fn titlebar_height(self, titlebar_height: u32) -> Self {
+        Self {
+            titlebar_height,
+            ..self
+        }
+    }
+
+    pub fn resize_border(self, resize_border: u32) -> Self {
+        Self {
+            resize_border,
+            ..self
+        }
+    }
+
+    pub fn active(self, active: DecorationColors) -> Self {
+        Self { active, ..self }
+    }
+
+    pub fn inactive(self, inactive: DecorationColors) -> Self {
+        Self { inactive, ..self }
+    }
+
+    pub fn title_painter(
+        self,
+        title_painter: impl Fn(&mut TitlebarCanvas<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            title_painter: Some(Arc::new(title_painter)),
+            ..self
+        }
+    }
+}
+
+/// A part of the titlebar of a window, in buffer pixels. The pixels are premultiplied ARGB, as
+/// `wl_shm` expects them.
+pub struct TitlebarCanvas<'a> {
+    pixels: &'a mut [u8],
+    /// The number of bytes per row of pixels.
+    stride: usize,
+    /// The position of the canvas in the pixels.
+    origin: (u32, u32),
+
+    width: u32,
+    height: u32,
+    scale: u32,
+
+    title: &'a str,
+    color: [u8; 4],
+}
+
+impl<'a> TitlebarCanvas<'a> {
+    pub(crate) fn new(
+        pixels: &'a mut [u8],
+        stride: usize,
+        origin: (u32, u32),
+        size: (u32, u32),
+        scale: u32,
+        title: &'a str,
+        color: [u8; 4],
+    ) -> Self {
+        Self {
+            pixels,
+            stride,
+            origin,
+            width: size.0,
+            height: size.1,
+            scale,
+            title,
+            color,
+        }
+    }
+
+    /// The width of the canvas, in buffer pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the canvas, in buffer pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of buffer pixels per logical pixel.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The title of the window.
+    pub fn title(&self) -> &str {
+        self.title
+    }
+
+    /// The color to draw the title with, which depends on whether the window is focused.
+    pub fn color(&self) -> [u8; 4] {
+        self.color
+    }
+
+    /// Draws a color over a pixel. Pixels outside of the canvas are ignored.
+    pub fn blend(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let offset = (self.origin.1 + y) as usize * self.stride + (self.origin.0 + x) as usize * 4;
+
+        let Some(pixel) = self.pixels.get_mut(offset..offset + 4) else {
+            return;
+        };
+
+        let [r, g, b, a] = color.map(u32::from);
+
+        // The pixels are stored as little endian ARGB, so as BGRA bytes.
+        for (channel, value) in pixel
+            .iter_mut()
+            .zip([b * a / 255, g * a / 255, r * a / 255, a])
+        {
+            *channel = (value + *channel as u32 * (255 - a) / 255) as u8;
+        }
+    }
+
+    /// Draws a color over a rectangle, clipped to the canvas.
+    pub fn fill(&mut self, rect: Rect, color: [u8; 4]) {
+        for y in rect.y..(rect.y + rect.height).min(self.height) {
+            for x in rect.x..(rect.x + rect.width).min(self.width) {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    /// Draws the title with the built-in font, left aligned and vertically centered, when the
+    /// theme has no painter. It stops at the first character that does not fit.
+    pub(crate) fn draw_title(&mut self) {
+        // The glyphs are scaled by whole dots, to about half of the height of the titlebar.
+        let size = (self.height / (font::GLYPH_HEIGHT * 2)).max(1);
+        let y = self.height.saturating_sub(font::GLYPH_HEIGHT * size) / 2;
+
+        let title = self.title;
+        let mut x = self.height / 3;
+
+        for c in title.chars() {
+            if x + font::GLYPH_WIDTH * size > self.width {
+                break;
+            }
+
+            let glyph = font::glyph(c);
+
+            for column in 0..font::GLYPH_WIDTH {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if font::dot(glyph, column, row) {
+                        let rect = Rect::new(x + column * size, y + row * size, size, size);
+
+                        self.fill(rect, self.color);
+                    }
+                }
+            }
+
+            x += (font::GLYPH_WIDTH + 1) * size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+    fn is_drawn(pixels: &[u8], width: u32, x: u32, y: u32) -> bool {
+        let offset = (y * width + x) as usize * 4;
+
+        pixels[offset..offset + 4] == WHITE
+    }
+
+    #[test]
+    fn draws_the_title_centered_vertically() {
+        let mut pixels = vec![0; 40 * 16 * 4];
+
+        TitlebarCanvas::new(&mut pixels, 40 * 4, (0, 0), (40, 16), 1, "I", WHITE).draw_title();
+
+        // The glyph is 7 dots high, the stem of 'I' is its middle column, 5 pixels in.
+        assert!((4..11).all(|y| is_drawn(&pixels, 40, 7, y)));
+        assert!(!is_drawn(&pixels, 40, 7, 3));
+        assert!(!is_drawn(&pixels, 40, 7, 11));
+        assert!(!is_drawn(&pixels, 40, 5, 7));
+    }
+
+    #[test]
+    fn scales_the_glyphs_with_the_titlebar() {
+        let mut pixels = vec![0; 80 * 32 * 4];
+
+        TitlebarCanvas::new(&mut pixels, 80 * 4, (0, 0), (80, 32), 2, "I", WHITE).draw_title();
+
+        // The dots are 2 pixels wide: the stem spans 2 columns and 14 rows.
+        assert!((9..23).all(|y| is_drawn(&pixels, 80, 14, y) && is_drawn(&pixels, 80, 15, y)));
+        assert!(!is_drawn(&pixels, 80, 16, 16));
+    }
+
+    #[test]
+    fn stops_at_the_first_character_that_does_not_fit() {
+        let mut pixels = vec![0; 15 * 16 * 4];
+
+        TitlebarCanvas::new(&mut pixels, 15 * 4, (0, 0), (15, 16), 1, "II", WHITE).draw_title();
+
+        assert!(is_drawn(&pixels, 15, 7, 7));
+        assert!(!is_drawn(&pixels, 15, 13, 7));
+    }
+}
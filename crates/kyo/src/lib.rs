@@ -1,4 +1,7 @@
 pub mod backend;
+pub mod config;
+pub(crate) mod damage;
+pub mod decorations;
 pub(crate) mod surface;
 pub(crate) mod widget;
 
@@ -6,11 +9,16 @@ pub mod prelude {
     pub use eyre::{Report, Result};
 
     pub use crate::backend::*;
+    pub use crate::config::*;
+    pub(crate) use crate::damage::*;
+    pub use crate::decorations::*;
     pub(crate) use crate::surface::*;
     pub(crate) use crate::widget::*;
 
     pub(crate) use reexport::*;
     pub mod reexport {
         pub use hej::prelude::{reexport::*, *};
+
+        pub use wgpu::{Backends, Limits, PowerPreference, PresentMode};
     }
 }
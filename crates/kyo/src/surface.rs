@@ -1,17 +1,34 @@
 use std::ptr::NonNull;
 
+use eyre::OptionExt;
 use raw_window_handle::{
     RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
 };
-use smithay_client_toolkit::shell::{WaylandSurface, wlr_layer::LayerSurface, xdg::window::Window};
+use smithay_client_toolkit::shell::{
+    WaylandSurface,
+    wlr_layer::LayerSurface,
+    xdg::{popup::Popup, window::Window},
+};
 use wayland_backend::client::ObjectId;
 use wayland_client::{Connection, Proxy, protocol::wl_surface::WlSurface};
 use wgpu::{
-    Adapter, Color, CompositeAlphaMode, Device, Instance, LoadOp, Operations, PresentMode, Queue,
-    RenderPassColorAttachment, RenderPassDescriptor, StoreOp, Surface, SurfaceConfiguration,
-    SurfaceTargetUnsafe, TextureUsages, TextureViewDescriptor,
+    Adapter, Color, CompositeAlphaMode, Device, Extent3d, Instance, LoadOp, Operations,
+    PresentMode, Queue, RenderPassColorAttachment, RenderPassDescriptor, StoreOp, Surface,
+    SurfaceConfiguration, SurfaceError, SurfaceTargetUnsafe, SurfaceTexture, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
 };
 
+use crate::prelude::*;
+
+/// Returns the first preferred value that is supported, or the fallback.
+fn select<T: Copy + PartialEq>(supported: &[T], preferred: &[T], fallback: T) -> T {
+    preferred
+        .iter()
+        .copied()
+        .find(|value| supported.contains(value))
+        .unwrap_or(fallback)
+}
+
 pub(crate) enum SurfaceHandle {
     Layer {
         layer: LayerSurface,
@@ -27,6 +44,13 @@ pub(crate) enum SurfaceHandle {
         device: Device,
         queue: Queue,
     },
+    Popup {
+        popup: Popup,
+        surface: Surface<'static>,
+        adapter: Adapter,
+        device: Device,
+        queue: Queue,
+    },
 }
 
 impl SurfaceHandle {
@@ -46,6 +70,13 @@ impl SurfaceHandle {
                 device: _,
                 queue: _,
             } => surface,
+            Self::Popup {
+                popup: _,
+                surface,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => surface,
         }
     }
 
@@ -65,6 +96,13 @@ impl SurfaceHandle {
                 device: _,
                 queue: _,
             } => adapter,
+            Self::Popup {
+                popup: _,
+                surface: _,
+                adapter,
+                device: _,
+                queue: _,
+            } => adapter,
         }
     }
 
@@ -84,6 +122,13 @@ impl SurfaceHandle {
                 device,
                 queue: _,
             } => device,
+            Self::Popup {
+                popup: _,
+                surface: _,
+                adapter: _,
+                device,
+                queue: _,
+            } => device,
         }
     }
 
@@ -103,43 +148,199 @@ impl SurfaceHandle {
                 device: _,
                 queue,
             } => queue,
+            Self::Popup {
+                popup: _,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue,
+            } => queue,
         }
     }
 
-    pub(crate) fn configure(&self, width: u32, height: u32) {
+    pub(crate) fn wl_surface(&self) -> &WlSurface {
+        match self {
+            Self::Layer {
+                layer,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => layer.wl_surface(),
+            Self::Window {
+                window,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => window.wl_surface(),
+            Self::Popup {
+                popup,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => popup.wl_surface(),
+        }
+    }
+
+    /// Negotiates a configuration with the surface capabilities and configures the surface.
+    /// Layers are expected to be transparent, so they prefer a premultiplied alpha. The first
+    /// supported present mode is used, Fifo being always supported. The buffers are copy
+    /// destinations when supported, so that a target texture can be copied to them.
+    pub(crate) fn configure(
+        &self,
+        width: u32,
+        height: u32,
+        present_modes: &[PresentMode],
+    ) -> Result<SurfaceConfiguration> {
         let cap = self.surface().get_capabilities(self.adapter());
 
+        let format = cap
+            .formats
+            .iter()
+            .copied()
+            .find(TextureFormat::is_srgb)
+            .or(cap.formats.first().copied())
+            .ok_or_eyre("The surface is not supported by the adapter")?;
+
+        let alpha_modes: &[CompositeAlphaMode] = match self {
+            Self::Layer { .. } => &[
+                CompositeAlphaMode::PreMultiplied,
+                CompositeAlphaMode::PostMultiplied,
+                CompositeAlphaMode::Inherit,
+            ],
+            Self::Window { .. } => &[CompositeAlphaMode::Opaque, CompositeAlphaMode::Inherit],
+            // Popups are often rounded or shadowed, like layers.
+            Self::Popup { .. } => &[
+                CompositeAlphaMode::PreMultiplied,
+                CompositeAlphaMode::PostMultiplied,
+                CompositeAlphaMode::Inherit,
+            ],
+        };
+
+        let usage = TextureUsages::RENDER_ATTACHMENT | (cap.usages & TextureUsages::COPY_DST);
+
         let surface_config = SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format: cap.formats[0],
-            view_formats: vec![cap.formats[0]],
-            alpha_mode: CompositeAlphaMode::Auto,
+            usage,
+            format,
+            view_formats: vec![format],
+            alpha_mode: select(&cap.alpha_modes, alpha_modes, CompositeAlphaMode::Auto),
             width,
             height,
             desired_maximum_frame_latency: 2,
-            present_mode: PresentMode::Mailbox,
+            present_mode: select(&cap.present_modes, present_modes, PresentMode::Fifo),
         };
 
+        tracing::debug!(
+            "Configuring surface with {:?}, {:?} and {:?}",
+            surface_config.format,
+            surface_config.present_mode,
+            surface_config.alpha_mode
+        );
+
         self.surface().configure(self.device(), &surface_config);
 
-        let surface_texture = self
-            .surface()
-            .get_current_texture()
-            .expect("failed to acquire next swapchain texture");
+        Ok(surface_config)
+    }
+
+    /// Creates the texture widgets are drawn into before being copied to the buffers of the
+    /// surface. It keeps its content between frames, unlike the buffers of the swapchain, so
+    /// that only the damaged regions have to be drawn. Returns `None` if the buffers cannot be
+    /// copied to, the surface is then fully redrawn every frame.
+    pub(crate) fn target(&self, config: &SurfaceConfiguration) -> Option<Texture> {
+        if !config.usage.contains(TextureUsages::COPY_DST) {
+            tracing::debug!("Surface buffers are not copy destinations, redrawing every frame");
+
+            return None;
+        }
+
+        Some(self.device().create_texture(&TextureDescriptor {
+            label: Some("kyo target"),
+            size: Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        }))
+    }
+
+    /// Acquires the next buffer of the surface, reconfiguring it once if it became outdated or
+    /// was lost.
+    fn acquire(&self, config: &SurfaceConfiguration) -> Result<SurfaceTexture> {
+        match self.surface().get_current_texture() {
+            Ok(texture) => Ok(texture),
+            Err(e @ (SurfaceError::Outdated | SurfaceError::Lost)) => {
+                tracing::warn!("Reconfiguring surface: {}", e);
+
+                self.surface().configure(self.device(), config);
+
+                Ok(self.surface().get_current_texture()?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Draws the element on the next buffer of the surface. Only the `damage` region of the
+    /// target is redrawn before the target is copied to the buffer. Without a target, the buffer
+    /// holds undefined content and is fully redrawn.
+    ///
+    /// The whole buffer is reported as damaged to the compositor: presenting through wgpu
+    /// always damages the entire surface.
+    pub(crate) fn render<Message: 'static + Send + Sync>(
+        &self,
+        element: &Element<Message>,
+        renderer: &mut nux::prelude::Renderer,
+        config: &SurfaceConfiguration,
+        target: Option<&Texture>,
+        scale: f64,
+        damage: &Damage,
+    ) -> Result<()> {
+        let (width, height) = (config.width, config.height);
+
+        let repaint = match target {
+            Some(_) => damage,
+            None => &Damage::Full,
+        };
+
+        let Some(bounds) = repaint.bounds(width, height) else {
+            self.wl_surface().commit();
+
+            return Ok(());
+        };
+
+        // The widget records its draw commands, they are issued on the render pass below.
+        renderer.begin_frame();
+
+        element.draw(
+            Canvas::new(width, height, scale, repaint.rects(width, height)),
+            &mut Renderer::new(scale).backend(renderer),
+        )?;
 
-        let texture_view = surface_texture
-            .texture
+        let surface_texture = self.acquire(config)?;
+
+        let texture_view = target
+            .unwrap_or(&surface_texture.texture)
             .create_view(&TextureViewDescriptor::default());
 
         let mut encoder = self.device().create_command_encoder(&Default::default());
         {
-            let _renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            let mut renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(RenderPassColorAttachment {
                     view: &texture_view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(Color::BLUE),
+                        load: match repaint {
+                            Damage::Full => LoadOp::Clear(Color::BLUE),
+                            _ => LoadOp::Load,
+                        },
                         store: StoreOp::Store,
                     },
                     depth_slice: None,
@@ -148,10 +349,58 @@ impl SurfaceHandle {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
+
+            // Only the repainted region is drawn, the rest of the target is kept.
+            renderpass.set_scissor_rect(bounds.x, bounds.y, bounds.width, bounds.height);
+
+            renderer.render(&mut renderpass)?;
+        }
+
+        if let Some(target) = target {
+            encoder.copy_texture_to_texture(
+                target.as_image_copy(),
+                surface_texture.texture.as_image_copy(),
+                target.size(),
+            );
         }
 
         self.queue().submit(Some(encoder.finish()));
+
+        let suboptimal = surface_texture.suboptimal;
+
         surface_texture.present();
+
+        if suboptimal {
+            self.surface().configure(self.device(), config);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn layer(&self) -> Option<&LayerSurface> {
+        match self {
+            Self::Layer {
+                layer,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => Some(layer),
+            Self::Window { .. } | Self::Popup { .. } => None,
+        }
+    }
+
+    pub(crate) fn window(&self) -> Option<&Window> {
+        match self {
+            Self::Window {
+                window,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => Some(window),
+            Self::Layer { .. } | Self::Popup { .. } => None,
+        }
     }
 
     pub(crate) fn id(&self) -> ObjectId {
@@ -170,6 +419,13 @@ impl SurfaceHandle {
                 device: _,
                 queue: _,
             } => window.wl_surface().id(),
+            Self::Popup {
+                popup,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => popup.wl_surface().id(),
         }
     }
 
@@ -193,6 +449,18 @@ impl SurfaceHandle {
             } => {
                 window.wl_surface().destroy();
             }
+            Self::Popup {
+                popup,
+                surface: _,
+                adapter: _,
+                device: _,
+                queue: _,
+            } => {
+                // The roles must be destroyed before the surface.
+                popup.xdg_popup().destroy();
+                popup.xdg_surface().destroy();
+                popup.wl_surface().destroy();
+            }
         }
     }
 
@@ -262,4 +530,25 @@ impl SurfaceHandle {
             queue,
         }
     }
+
+    pub(crate) fn from_popup(
+        popup: Popup,
+
+        instance: Instance,
+        connection: Connection,
+
+        adapter: Adapter,
+        device: Device,
+        queue: Queue,
+    ) -> Self {
+        let surface = Self::wgpu_surface(popup.wl_surface(), instance, connection);
+
+        Self::Popup {
+            popup,
+            surface,
+            adapter,
+            device,
+            queue,
+        }
+    }
 }
@@ -1,24 +1,97 @@
+use smithay_client_toolkit::reexports::protocols::wp::{
+    fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1,
+    viewporter::client::wp_viewport::WpViewport,
+};
+use smithay_client_toolkit::shell::WaylandSurface;
 use wayland_backend::client::ObjectId;
+use wayland_client::QueueHandle;
+use wgpu::{PresentMode, SurfaceConfiguration, Texture};
 
 use crate::prelude::*;
 
 pub(crate) struct WaylandWidget<Message> {
     pub(crate) id: ObjectId,
+    pub(crate) label: String,
+    /// The name of the output the surface is placed on, the compositor's choice when `None`.
+    pub(crate) output: Option<String>,
+    /// The label of the per output widget this surface is an instance of.
+    pub(crate) replica_of: Option<String>,
+    /// The label of the surface a popup is placed against, destroyed along with it.
+    pub(crate) parent: Option<String>,
+    /// The title of a window, drawn by its decorations. The label when `None`.
+    pub(crate) title: Option<String>,
     pub(crate) surface: SurfaceHandle,
+    pub(crate) qh: QueueHandle<State<Message>>,
+
+    /// The size requested from the compositor, used when it lets us choose.
+    pub(crate) requested: (u32, u32),
+    /// The size of the surface, in logical pixels.
+    pub(crate) size: (u32, u32),
+    /// The number of buffer pixels per logical pixel.
+    pub(crate) scale: f64,
+    /// Scales the buffer to the logical size, so the scale may be fractional.
+    pub(crate) viewport: Option<WpViewport>,
+    pub(crate) fractional_scale: Option<WpFractionalScaleV1>,
+    pub(crate) config: Option<SurfaceConfiguration>,
+    pub(crate) present_modes: Vec<PresentMode>,
+    /// The texture the widget is drawn into, copied to the buffers of the surface.
+    pub(crate) target: Option<Texture>,
+    pub(crate) damage: DamageTracker,
+    /// Batches the draw commands of the widget into instanced draw calls.
+    pub(crate) renderer: nux::prelude::Renderer,
+    pub(crate) frame_pending: bool,
+
+    /// Recognizes the gestures sent to the widget after the pointer events.
+    pub(crate) gestures: GestureRecognizer,
 
     pub(crate) widget: Element<Message>,
 }
 
 impl<Message: 'static + Send + Sync> WaylandWidget<Message> {
-    pub(crate) fn new(surface: SurfaceHandle, widget: Element<Message>) -> Self {
+    pub(crate) fn new(
+        label: String,
+        surface: SurfaceHandle,
+        requested: (u32, u32),
+        widget: Element<Message>,
+        present_modes: Vec<PresentMode>,
+        qh: QueueHandle<State<Message>>,
+    ) -> Self {
+        let renderer = nux::prelude::Renderer::new(surface.device(), surface.queue());
+
         Self {
             id: surface.id(),
+            label,
+            output: None,
+            replica_of: None,
+            parent: None,
+            title: None,
             surface,
+            qh,
+            requested,
+            size: (0, 0),
+            scale: 1.0,
+            viewport: None,
+            fractional_scale: None,
+            config: None,
+            present_modes,
+            target: None,
+            damage: DamageTracker::default(),
+            renderer,
+            frame_pending: false,
+            gestures: GestureRecognizer::default(),
             widget,
         }
     }
 
     pub(crate) fn destroy(&self) {
+        if let Some(fractional_scale) = &self.fractional_scale {
+            fractional_scale.destroy();
+        }
+
+        if let Some(viewport) = &self.viewport {
+            viewport.destroy();
+        }
+
         self.surface.destroy();
     }
 
@@ -28,19 +101,236 @@ impl<Message: 'static + Send + Sync> WaylandWidget<Message> {
         submitter: Submitter<Message>,
     ) -> Option<String> {
         match event {
-            Event::Close => self.widget.label(),
+            Event::Close => {
+                if let Err(e) = self.widget.on_event(Event::Close, submitter) {
+                    tracing::error!("Error {}", e);
+                }
+
+                // Only a window survives the request, the compositor already removed the others.
+                if self.widget.handles_close() && self.surface.window().is_some() {
+                    return None;
+                }
+
+                return Some(self.label.clone());
+            }
             Event::Configure { width, height } => {
-                self.surface.configure(width, height);
+                // A size of 0 means the compositor lets us choose, so we use the requested one.
+                let width = if width == 0 { self.requested.0 } else { width };
+                let height = if height == 0 {
+                    self.requested.1
+                } else {
+                    height
+                };
+
+                if width == 0 || height == 0 {
+                    tracing::warn!("Cannot configure a surface without a size");
+
+                    return None;
+                }
+
+                self.size = (width, height);
 
-                None
+                // A configure must be answered with a new buffer, so we don't wait for a frame.
+                self.reconfigure();
             }
-            event => {
-                if let Err(e) = self.widget.on_event(event, submitter) {
+            Event::ScaleChanged { scale } => {
+                if scale <= 0.0 || scale == self.scale {
+                    return None;
+                }
+
+                self.scale = scale;
+
+                if let Err(e) = self
+                    .widget
+                    .on_event(Event::ScaleChanged { scale }, submitter)
+                {
                     tracing::error!("Error {}", e);
                 }
 
-                None
+                if self.config.is_some() {
+                    self.reconfigure();
+                }
+            }
+            Event::Render => {
+                self.frame_pending = false;
+
+                if let Err(e) = self.widget.on_event(Event::Render, submitter) {
+                    tracing::error!("Error {}", e);
+                }
+
+                let damage = self.widget.damage();
+
+                // The widget is animating, it will draw the next frame too.
+                if !damage.is_none() {
+                    self.request_frame();
+                }
+
+                self.damage.add(damage);
+
+                if self.damage.is_dirty() {
+                    self.render();
+                }
+            }
+            event => {
+                let gestures = self.gestures.handle(&event, std::time::Instant::now());
+
+                for event in std::iter::once(event).chain(gestures) {
+                    if let Err(e) = self.widget.on_event(event, submitter.clone()) {
+                        tracing::error!("Error {}", e);
+                    }
+                }
+
+                self.damage.add(self.widget.damage());
+                self.schedule();
+            }
+        }
+
+        None
+    }
+
+    /// Sends the long presses that are due to the widget.
+    pub(crate) fn expire_gestures(&mut self, submitter: Submitter<Message>) {
+        let gestures = self.gestures.expire(std::time::Instant::now());
+
+        if gestures.is_empty() {
+            return;
+        }
+
+        for event in gestures {
+            if let Err(e) = self.widget.on_event(event, submitter.clone()) {
+                tracing::error!("Error {}", e);
             }
         }
+
+        self.damage.add(self.widget.damage());
+        self.schedule();
+    }
+
+    /// Matches a key press against the shortcuts of the widget, and returns whether they consumed
+    /// it. The widget may have changed, so it is redrawn if needed.
+    pub(crate) fn on_shortcut(&mut self, event: &KeyEvent, submitter: Submitter<Message>) -> bool {
+        let consumed = self
+            .widget
+            .on_shortcut(event, submitter)
+            .unwrap_or_else(|e| {
+                tracing::error!("Error {}", e);
+
+                false
+            });
+
+        self.damage.add(self.widget.damage());
+        self.schedule();
+
+        consumed
+    }
+
+    /// The size of the buffers of the surface, in buffer pixels.
+    pub(crate) fn buffer_size(&self) -> (u32, u32) {
+        (
+            (self.size.0 as f64 * self.scale).round() as u32,
+            (self.size.1 as f64 * self.scale).round() as u32,
+        )
+    }
+
+    /// Configures the surface for its current size and scale, and renders a new buffer.
+    pub(crate) fn reconfigure(&mut self) {
+        let (width, height) = self.buffer_size();
+
+        match self.surface.configure(width, height, &self.present_modes) {
+            Ok(config) => {
+                self.target = self.surface.target(&config);
+                self.config = Some(config);
+            }
+            Err(e) => {
+                tracing::error!("Failed to configure surface: {}", e);
+
+                return;
+            }
+        }
+
+        // Without a viewport, only integer scales can be applied to the buffer.
+        match &self.viewport {
+            Some(viewport) => viewport.set_destination(self.size.0 as i32, self.size.1 as i32),
+            None => self
+                .surface
+                .wl_surface()
+                .set_buffer_scale(self.scale.round() as i32),
+        }
+
+        self.damage.reset();
+        self.render();
+    }
+
+    /// Resolves the layout against a new output size. Layer surfaces with a relative size are
+    /// resized, the compositor then sends a new configure.
+    pub(crate) fn resize(&mut self, output: (u32, u32)) {
+        let layout = self.widget.layout();
+
+        if !layout.is_relative() {
+            return;
+        }
+
+        self.requested = layout.size(output);
+
+        if let Some(layer) = self.surface.layer() {
+            layer.set_size(self.requested.0, self.requested.1);
+            layer.set_exclusive_zone(layout.resolve_exclusive_zone(self.requested));
+            layer.commit();
+        }
+    }
+
+    /// Marks the whole surface as damaged and schedules a redraw.
+    pub(crate) fn request_redraw(&mut self) {
+        self.damage.add(Damage::Full);
+        self.schedule();
+    }
+
+    /// Requests a frame callback if the surface is dirty, the widget will be drawn when the
+    /// compositor is ready for a new frame. Nothing is requested for idle surfaces.
+    pub(crate) fn schedule(&mut self) {
+        if self.frame_pending || !self.damage.is_dirty() || self.config.is_none() {
+            return;
+        }
+
+        self.request_frame();
+        self.surface.wl_surface().commit();
+    }
+
+    /// Asks the compositor for a `Render` event when it is ready for a new frame. The request is
+    /// applied by the next commit.
+    fn request_frame(&mut self) {
+        let wl_surface = self.surface.wl_surface();
+
+        wl_surface.frame(&self.qh, wl_surface.clone());
+
+        self.frame_pending = true;
+    }
+
+    pub(crate) fn render(&mut self) {
+        let Some(config) = &self.config else {
+            return;
+        };
+
+        let damage = self.damage.take();
+
+        if let Err(e) = self.surface.render(
+            &self.widget,
+            &mut self.renderer,
+            config,
+            self.target.as_ref(),
+            self.scale,
+            &damage,
+        ) {
+            tracing::error!("Failed to render surface: {}", e);
+
+            // Nothing was presented: retry on the next frame.
+            self.damage.add(Damage::Full);
+
+            if !self.frame_pending {
+                self.request_frame();
+            }
+
+            self.surface.wl_surface().commit();
+        }
     }
 }
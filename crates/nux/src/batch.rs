@@ -0,0 +1,194 @@
+//! This module defines draw commands and the `Batcher` that sorts them by pipeline and texture
+//! and merges them into instanced draw calls.
+
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{VertexBufferLayout, VertexStepMode, vertex_attr_array};
+
+/// Identifies a render pipeline registered on the `Renderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PipelineId(pub u32);
+
+/// Identifies a texture bind group registered on the `Renderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureId(pub u32);
+
+/// Identifies a mesh uploaded for the current frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MeshId(pub u32);
+
+/// Per-instance data uploaded to the instance buffer. A pipeline used with nux must read it from
+/// the second vertex buffer slot, see `Instance::LAYOUT`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct Instance {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 4],
+    pub uv: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        vertex_attr_array![8 => Float32x2, 9 => Float32x2, 10 => Float32x4, 11 => Float32x4];
+
+    /// The vertex buffer layout of the instance buffer, bound at slot 1.
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: std::mem::size_of::<Instance>() as u64,
+        step_mode: VertexStepMode::Instance,
+        attributes: &Self::ATTRIBUTES,
+    };
+}
+
+/// A single primitive to draw. Commands are drawn in increasing `z` order, commands that share
+/// the same `z` may be reordered to be merged together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawCommand {
+    pub z: i32,
+
+    pub pipeline: PipelineId,
+    /// The texture bound at group 0. Without one, the renderer binds its default texture, or
+    /// nothing if none was set, in which case the pipeline must not read group 0.
+    pub texture: Option<TextureId>,
+    pub mesh: MeshId,
+
+    pub instance: Instance,
+}
+
+impl DrawCommand {
+    fn key(&self) -> (i32, PipelineId, Option<TextureId>, MeshId) {
+        (self.z, self.pipeline, self.texture, self.mesh)
+    }
+}
+
+/// An instanced draw call produced by the `Batcher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawCall {
+    pub pipeline: PipelineId,
+    pub texture: Option<TextureId>,
+    pub mesh: MeshId,
+
+    /// The range of instances to draw, relative to the start of the frame's instance data.
+    pub instances: Range<u32>,
+}
+
+/// The result of batching a frame: the draw calls to issue and the instance data they refer to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Batch {
+    pub calls: Vec<DrawCall>,
+    pub instances: Vec<Instance>,
+}
+
+/// Collects draw commands for a frame and merges them into as few draw calls as possible.
+#[derive(Debug, Default)]
+pub struct Batcher {
+    commands: Vec<DrawCommand>,
+}
+
+impl Batcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: DrawCommand) {
+        self.commands.push(command);
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Sorts the pending commands by z, pipeline, texture and mesh, then merges consecutive
+    /// commands sharing the same state into a single instanced draw call. The batcher is empty
+    /// afterwards and can be reused for the next frame.
+    pub fn build(&mut self) -> Batch {
+        // The sort is stable so commands with the same key keep their submission order.
+        self.commands.sort_by_key(DrawCommand::key);
+
+        let mut batch = Batch {
+            calls: Vec::new(),
+            instances: Vec::with_capacity(self.commands.len()),
+        };
+
+        for command in self.commands.drain(..) {
+            let index = batch.instances.len() as u32;
+            batch.instances.push(command.instance);
+
+            match batch.calls.last_mut() {
+                Some(call)
+                    if call.pipeline == command.pipeline
+                        && call.texture == command.texture
+                        && call.mesh == command.mesh =>
+                {
+                    call.instances.end = index + 1;
+                }
+                _ => batch.calls.push(DrawCall {
+                    pipeline: command.pipeline,
+                    texture: command.texture,
+                    mesh: command.mesh,
+                    instances: index..index + 1,
+                }),
+            }
+        }
+
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(z: i32, pipeline: u32, texture: Option<u32>) -> DrawCommand {
+        DrawCommand {
+            z,
+            pipeline: PipelineId(pipeline),
+            texture: texture.map(TextureId),
+            mesh: MeshId(0),
+            instance: Instance::default(),
+        }
+    }
+
+    #[test]
+    fn merges_commands_sharing_state() {
+        let mut batcher = Batcher::new();
+
+        batcher.push(command(0, 1, Some(0)));
+        batcher.push(command(0, 0, None));
+        batcher.push(command(0, 1, Some(0)));
+        batcher.push(command(0, 0, None));
+
+        let batch = batcher.build();
+
+        assert_eq!(batch.instances.len(), 4);
+        assert_eq!(batch.calls.len(), 2);
+        assert_eq!(batch.calls[0].pipeline, PipelineId(0));
+        assert_eq!(batch.calls[0].instances, 0..2);
+        assert_eq!(batch.calls[1].instances, 2..4);
+        assert!(batcher.is_empty());
+    }
+
+    #[test]
+    fn keeps_z_order() {
+        let mut batcher = Batcher::new();
+
+        batcher.push(command(1, 0, None));
+        batcher.push(command(0, 1, None));
+        batcher.push(command(2, 0, None));
+
+        let batch = batcher.build();
+
+        let pipelines: Vec<_> = batch.calls.iter().map(|call| call.pipeline.0).collect();
+        assert_eq!(pipelines, vec![1, 0]);
+        assert_eq!(batch.calls[1].instances, 1..3);
+    }
+}
@@ -1,14 +1,23 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod batch;
+pub mod ring;
+pub mod stats;
+
+pub mod renderer;
+
+pub mod prelude {
+    //! A collection of commonly used types for rendering with nux.
+
+    pub use eyre::{Report, Result};
+
+    pub use crate::batch::*;
+    pub use crate::ring::*;
+    pub use crate::stats::*;
+
+    pub use crate::renderer::*;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub mod reexport {
+        //! A collection of re-exports for commonly used types and traits.
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+        pub use chii::prelude::*;
     }
 }
@@ -0,0 +1,207 @@
+//! This module defines the `Renderer`, which records draw commands for a frame, batches them and
+//! issues the resulting instanced draw calls on a render pass.
+
+use std::ops::Range;
+
+use eyre::OptionExt;
+use wgpu::{BindGroup, BufferUsages, Device, Queue, RenderPass, RenderPipeline};
+
+use crate::prelude::*;
+
+/// The number of frames that may be in flight at once, and so the number of segments of the
+/// ring buffers.
+const FRAMES_IN_FLIGHT: u64 = 3;
+
+struct Mesh {
+    range: Range<u64>,
+    vertices: u32,
+}
+
+/// A 2d renderer that batches draw commands into instanced draw calls.
+///
+/// Pipelines must read mesh vertices from slot 0 and `Instance`s from slot 1, and textures are
+/// bound at group 0.
+pub struct Renderer {
+    device: Device,
+    queue: Queue,
+
+    pipelines: Vec<RenderPipeline>,
+    textures: Vec<BindGroup>,
+    default_texture: Option<TextureId>,
+
+    meshes: Vec<Mesh>,
+    batcher: Batcher,
+
+    vertices: RingBuffer,
+    instances: RingBuffer,
+
+    frame: FrameStats,
+    statistics: Statistics,
+}
+
+impl Renderer {
+    /// Creates a renderer drawing with the device and queue of a surface.
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        Self {
+            device: device.clone(),
+            queue: queue.clone(),
+
+            pipelines: Vec::new(),
+            textures: Vec::new(),
+            default_texture: None,
+
+            meshes: Vec::new(),
+            batcher: Batcher::new(),
+
+            vertices: RingBuffer::new("nux vertices", BufferUsages::VERTEX, FRAMES_IN_FLIGHT),
+            instances: RingBuffer::new("nux instances", BufferUsages::VERTEX, FRAMES_IN_FLIGHT),
+
+            frame: FrameStats::default(),
+            statistics: Statistics::default(),
+        }
+    }
+
+    /// The device the renderer draws with, to create pipelines and textures.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Registers a render pipeline to be referenced by draw commands.
+    pub fn pipeline(&mut self, pipeline: RenderPipeline) -> PipelineId {
+        self.pipelines.push(pipeline);
+
+        PipelineId(self.pipelines.len() as u32 - 1)
+    }
+
+    /// Registers a texture bind group to be referenced by draw commands.
+    pub fn texture(&mut self, bind_group: BindGroup) -> TextureId {
+        self.textures.push(bind_group);
+
+        TextureId(self.textures.len() as u32 - 1)
+    }
+
+    /// Sets the texture bound for draw commands without a texture, typically a 1x1 white
+    /// texture so that textured pipelines draw plain colors.
+    pub fn default_texture(&mut self, texture: TextureId) {
+        self.default_texture = Some(texture);
+    }
+
+    /// Returns a handle to the statistics of the last rendered frame.
+    pub fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// Starts a new frame. Meshes and draw commands of the previous frame are discarded.
+    pub fn begin_frame(&mut self) {
+        self.meshes.clear();
+        self.batcher.clear();
+
+        self.vertices.begin_frame();
+        self.instances.begin_frame();
+
+        self.frame = FrameStats {
+            frame: self.frame.frame + 1,
+            atlas_used: self.frame.atlas_used,
+            atlas_capacity: self.frame.atlas_capacity,
+            ..Default::default()
+        };
+    }
+
+    /// Uploads the vertices of a mesh for the current frame.
+    pub fn mesh(&mut self, vertices: &[u8], count: u32) -> MeshId {
+        self.meshes.push(Mesh {
+            range: self.vertices.push(vertices),
+            vertices: count,
+        });
+
+        MeshId(self.meshes.len() as u32 - 1)
+    }
+
+    /// Records a draw command for the current frame.
+    pub fn draw(&mut self, command: DrawCommand) {
+        self.batcher.push(command);
+    }
+
+    /// Reports the texture atlas usage, in texels, for the statistics.
+    pub fn atlas(&mut self, used: u64, capacity: u64) {
+        self.frame.atlas_used = used;
+        self.frame.atlas_capacity = capacity;
+    }
+
+    /// Batches the recorded commands, uploads the vertex and instance data and issues the draw
+    /// calls on the provided render pass.
+    pub fn render(&mut self, pass: &mut RenderPass) -> Result<()> {
+        self.frame.commands = self.batcher.len() as u32;
+
+        let batch = self.batcher.build();
+        let instances = self.instances.push(bytemuck::cast_slice(&batch.instances));
+
+        for (uploaded, reallocated) in [
+            self.vertices.flush(&self.device, &self.queue),
+            self.instances.flush(&self.device, &self.queue),
+        ] {
+            self.frame.uploaded_bytes += uploaded;
+            self.frame.buffer_reallocations += reallocated as u32;
+        }
+
+        let mut pipeline = None;
+        let mut texture = None;
+
+        for call in &batch.calls {
+            if pipeline != Some(call.pipeline) {
+                pass.set_pipeline(
+                    self.pipelines
+                        .get(call.pipeline.0 as usize)
+                        .ok_or_eyre("Unknown pipeline")?,
+                );
+
+                pipeline = Some(call.pipeline);
+            }
+
+            if let Some(id) = call.texture.or(self.default_texture)
+                && texture != Some(id)
+            {
+                pass.set_bind_group(
+                    0,
+                    self.textures
+                        .get(id.0 as usize)
+                        .ok_or_eyre("Unknown texture")?,
+                    &[],
+                );
+
+                texture = Some(id);
+            }
+
+            let mesh = self
+                .meshes
+                .get(call.mesh.0 as usize)
+                .ok_or_eyre("Unknown mesh")?;
+
+            pass.set_vertex_buffer(
+                0,
+                self.vertices
+                    .slice(mesh.range.clone())
+                    .ok_or_eyre("Vertex buffer was not uploaded")?,
+            );
+            pass.set_vertex_buffer(
+                1,
+                self.instances
+                    .slice(instances.clone())
+                    .ok_or_eyre("Instance buffer was not uploaded")?,
+            );
+
+            pass.draw(0..mesh.vertices, call.instances.clone());
+
+            self.frame.draw_calls += 1;
+            self.frame.instances += call.instances.len() as u32;
+        }
+
+        self.statistics.publish(self.frame);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! This module defines a `RingBuffer`, a GPU buffer split into one segment per frame in flight so
+//! that vertex and instance data can be reused across frames without stalling on the GPU.
+
+use std::ops::Range;
+
+use wgpu::{
+    Buffer, BufferDescriptor, BufferSlice, BufferUsages, COPY_BUFFER_ALIGNMENT, Device, Queue,
+};
+
+/// A GPU buffer reused across frames. Data is staged on the CPU during the frame and uploaded
+/// in one write by `RingBuffer::flush`, into the segment of the current frame.
+pub struct RingBuffer {
+    label: &'static str,
+    usage: BufferUsages,
+
+    buffer: Option<Buffer>,
+    segment_size: u64,
+    segments: u64,
+    segment: u64,
+
+    staging: Vec<u8>,
+}
+
+impl RingBuffer {
+    /// Creates a ring buffer with `segments` segments, one per frame in flight.
+    pub fn new(label: &'static str, usage: BufferUsages, segments: u64) -> Self {
+        Self {
+            label,
+            usage: usage | BufferUsages::COPY_DST,
+            buffer: None,
+            segment_size: 0,
+            segments: segments.max(1),
+            segment: 0,
+            staging: Vec::new(),
+        }
+    }
+
+    /// Moves to the next segment and discards the staged data.
+    pub fn begin_frame(&mut self) {
+        self.segment = (self.segment + 1) % self.segments;
+        self.staging.clear();
+    }
+
+    /// Stages `data` and returns its byte range relative to the start of the frame's data.
+    pub fn push(&mut self, data: &[u8]) -> Range<u64> {
+        let start = self.staging.len() as u64;
+        self.staging.extend_from_slice(data);
+
+        let end = self.staging.len() as u64;
+        self.staging.resize(align(end) as usize, 0);
+
+        start..end
+    }
+
+    /// Uploads the staged data, growing the buffer if it does not fit in a segment. Returns the
+    /// number of uploaded bytes and whether the buffer had to be reallocated.
+    pub fn flush(&mut self, device: &Device, queue: &Queue) -> (u64, bool) {
+        let size = self.staging.len() as u64;
+
+        if size == 0 {
+            return (0, false);
+        }
+
+        let reallocated = self.buffer.is_none() || size > self.segment_size;
+
+        if reallocated {
+            self.segment_size = grow(self.segment_size, size);
+            self.buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some(self.label),
+                size: self.segment_size * self.segments,
+                usage: self.usage,
+                mapped_at_creation: false,
+            }));
+
+            tracing::debug!(
+                "Reallocated ring buffer {} with {} bytes per segment",
+                self.label,
+                self.segment_size
+            );
+        }
+
+        if let Some(buffer) = &self.buffer {
+            queue.write_buffer(buffer, self.base(), &self.staging);
+        }
+
+        (size, reallocated)
+    }
+
+    /// Returns a slice of the current segment, `range` being relative to the frame's data.
+    pub fn slice(&self, range: Range<u64>) -> Option<BufferSlice<'_>> {
+        let base = self.base();
+
+        self.buffer
+            .as_ref()
+            .map(|buffer| buffer.slice(base + range.start..base + range.end))
+    }
+
+    /// The total size of the underlying GPU buffer, in bytes.
+    pub fn capacity(&self) -> u64 {
+        self.segment_size * self.segments
+    }
+
+    fn base(&self) -> u64 {
+        self.segment * self.segment_size
+    }
+}
+
+fn align(size: u64) -> u64 {
+    size.div_ceil(COPY_BUFFER_ALIGNMENT) * COPY_BUFFER_ALIGNMENT
+}
+
+/// The segment size after growing a segment of `current` bytes to hold `size` bytes. Doubling
+/// keeps the number of reallocations logarithmic when a frame keeps growing.
+fn grow(current: u64, size: u64) -> u64 {
+    size.next_power_of_two().max(current * 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(segments: u64) -> RingBuffer {
+        RingBuffer::new("test", BufferUsages::VERTEX, segments)
+    }
+
+    #[test]
+    fn aligns_pushed_data() {
+        let mut ring = ring(3);
+
+        assert_eq!(ring.push(&[1, 2, 3]), 0..3);
+        assert_eq!(ring.push(&[4; 8]), 4..12);
+        assert_eq!(ring.staging.len(), 12);
+
+        ring.begin_frame();
+
+        assert!(ring.staging.is_empty());
+        assert_eq!(ring.push(&[5]), 0..1);
+    }
+
+    #[test]
+    fn wraps_around_segments() {
+        let mut ring = ring(3);
+        ring.segment_size = 64;
+
+        let bases: Vec<_> = (0..4)
+            .map(|_| {
+                ring.begin_frame();
+                ring.base()
+            })
+            .collect();
+
+        assert_eq!(bases, vec![64, 128, 0, 64]);
+        assert_eq!(ring.capacity(), 192);
+    }
+
+    #[test]
+    fn keeps_at_least_one_segment() {
+        let mut ring = ring(0);
+        ring.segment_size = 64;
+
+        ring.begin_frame();
+
+        assert_eq!(ring.base(), 0);
+        assert_eq!(ring.capacity(), 64);
+    }
+
+    #[test]
+    fn grows_to_a_power_of_two() {
+        assert_eq!(grow(0, 12), 16);
+        assert_eq!(grow(0, 16), 16);
+        assert_eq!(grow(16, 20), 32);
+        assert_eq!(grow(32, 40), 64);
+        assert_eq!(grow(64, 1000), 1024);
+    }
+}
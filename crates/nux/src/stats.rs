@@ -0,0 +1,90 @@
+//! This module defines the per-frame statistics gathered by the `Renderer`.
+
+use std::sync::{Arc, Mutex};
+
+/// Statistics of a single rendered frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// The index of the frame, starting at 1 for the first rendered frame.
+    pub frame: u64,
+
+    /// The number of draw commands submitted before batching.
+    pub commands: u32,
+    /// The number of draw calls issued after batching.
+    pub draw_calls: u32,
+    /// The number of instances drawn.
+    pub instances: u32,
+
+    /// The number of bytes uploaded to the GPU for vertices and instances.
+    pub uploaded_bytes: u64,
+    /// The number of times a ring buffer had to grow during the frame.
+    pub buffer_reallocations: u32,
+
+    /// The number of texels in use in the texture atlas.
+    pub atlas_used: u64,
+    /// The total number of texels in the texture atlas.
+    pub atlas_capacity: u64,
+}
+
+impl FrameStats {
+    /// The fraction of the texture atlas in use, between 0 and 1.
+    pub fn atlas_usage(&self) -> f32 {
+        if self.atlas_capacity == 0 {
+            return 0.0;
+        }
+
+        self.atlas_used as f32 / self.atlas_capacity as f32
+    }
+}
+
+/// A handle to the statistics of the last rendered frame. It can be cloned and kept by the
+/// application to track regressions while the renderer keeps drawing.
+#[derive(Debug, Default, Clone)]
+pub struct Statistics {
+    pub(crate) last: Arc<Mutex<FrameStats>>,
+}
+
+impl Statistics {
+    /// Returns the statistics of the last rendered frame.
+    pub fn last_frame(&self) -> FrameStats {
+        self.last.lock().map(|stats| *stats).unwrap_or_default()
+    }
+
+    pub(crate) fn publish(&self, stats: FrameStats) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = stats;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atlas_usage_is_a_fraction() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.atlas_usage(), 0.0);
+
+        stats.atlas_used = 256;
+        stats.atlas_capacity = 1024;
+        assert_eq!(stats.atlas_usage(), 0.25);
+    }
+
+    #[test]
+    fn publishes_to_every_handle() {
+        let statistics = Statistics::default();
+        let handle = statistics.clone();
+
+        assert_eq!(handle.last_frame(), FrameStats::default());
+
+        let stats = FrameStats {
+            frame: 3,
+            draw_calls: 2,
+            ..Default::default()
+        };
+        statistics.publish(stats);
+
+        assert_eq!(handle.last_frame(), stats);
+    }
+}